@@ -0,0 +1,24 @@
+use std::hint::black_box;
+use std::time::Instant;
+
+#[allow(dead_code, unused_imports)]
+#[path = "../src/d11.rs"]
+mod d11;
+
+fn bench(name: &str, iterations: u32, f: impl Fn() -> String) {
+    black_box(f());
+    let start = Instant::now();
+    for _ in 0..iterations {
+        black_box(f());
+    }
+    println!("{name}: {:?} per iteration", start.elapsed() / iterations);
+}
+
+fn main() {
+    let input = include_str!("../../data/d11.txt");
+
+    bench("d11 75 blinks", 20, || d11::part2(input));
+    bench("d11 500 blinks", 5, || {
+        d11::command(input, &["--blinks", "500"]).unwrap()
+    });
+}
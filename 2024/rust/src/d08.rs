@@ -1,68 +1,219 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::iter;
 
 use itertools::Itertools;
 
+type Pos = (i64, i64, i64);
+
 struct Map {
-    size: (usize, usize),
-    antennas: HashMap<char, Vec<(i64, i64)>>,
+    size: (usize, usize, usize),
+    antennas: HashMap<char, Vec<Pos>>,
+}
+
+impl Map {
+    fn contains(&self, (l, i, j): Pos) -> bool {
+        (0..self.size.0 as i64).contains(&l)
+            && (0..self.size.1 as i64).contains(&i)
+            && (0..self.size.2 as i64).contains(&j)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Rule {
+    // An antinode lies on the far side of an antenna, `k` times as far from the
+    // other antenna of the pair as from it.
+    Ratio(i64),
+    // Any position in line with two antennas, regardless of distance.
+    Resonant,
 }
 
+// Layers of a 3-D map are blocks of the input separated by blank lines, so a
+// plain puzzle input is a map with a single layer.
 fn parse(input: &str) -> Map {
-    let lines: Vec<&str> = input.trim().lines().collect();
-    let rows = lines.len();
-    let cols = lines.get(0).unwrap().len();
-    let mut antennas: HashMap<char, Vec<(i64, i64)>> = HashMap::new();
-    lines.iter().enumerate().for_each(|(i, line)| {
-        line.trim().char_indices().for_each(|(j, char)| {
-            if char != '.' {
-                antennas.entry(char).or_default().push((i as i64, j as i64))
-            }
+    let layers: Vec<Vec<&str>> = input
+        .trim()
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .split(|line| line.is_empty())
+        .filter(|layer| !layer.is_empty())
+        .map(|layer| layer.to_vec())
+        .collect();
+    let rows = layers.first().unwrap().len();
+    let cols = layers.first().unwrap().first().unwrap().len();
+    assert!(
+        layers
+            .iter()
+            .all(|layer| layer.len() == rows && layer.iter().all(|line| line.len() == cols)),
+        "all layers must have the same size"
+    );
+
+    let mut antennas: HashMap<char, Vec<Pos>> = HashMap::new();
+    layers.iter().enumerate().for_each(|(l, layer)| {
+        layer.iter().enumerate().for_each(|(i, line)| {
+            line.char_indices().for_each(|(j, char)| {
+                if char != '.' {
+                    antennas
+                        .entry(char)
+                        .or_default()
+                        .push((l as i64, i as i64, j as i64))
+                }
+            })
         })
     });
 
     Map {
-        size: (rows, cols),
+        size: (layers.len(), rows, cols),
         antennas,
     }
 }
 
-pub fn part1(input: &str) -> String {
-    let map = parse(input);
+fn antinodes(map: &Map, antennas: &[Pos], rule: Rule) -> HashSet<Pos> {
+    antennas
+        .iter()
+        .permutations(2)
+        .flat_map(|pair| {
+            let ((l1, i1, j1), (l2, i2, j2)) = (*pair[0], *pair[1]);
+            let (dl, di, dj) = (l2 - l1, i2 - i1, j2 - j1);
+            let (step, multiples) = match rule {
+                Rule::Ratio(k) if [dl, di, dj].iter().all(|d| d % (k - 1) == 0) => {
+                    ((dl / (k - 1), di / (k - 1), dj / (k - 1)), 1..2)
+                }
+                Rule::Ratio(_) => ((0, 0, 0), 0..0),
+                Rule::Resonant => ((dl, di, dj), 0..i64::MAX),
+            };
+            multiples
+                .map(move |n| (l2 + n * step.0, i2 + n * step.1, j2 + n * step.2))
+                .take_while(|pos| map.contains(*pos))
+        })
+        .collect()
+}
 
+fn antinodes_by_freq(map: &Map, rule: Rule) -> BTreeMap<char, HashSet<Pos>> {
     map.antennas
-        .values()
-        .flat_map(|freq_antennas| {
-            freq_antennas.iter().permutations(2).map(|pair| {
-                let ((i1, j1), (i2, j2)) = (pair[0], pair[1]);
-                let (di, dj) = (i2 - i1, j2 - j1);
-                (i2 + di, j2 + dj)
-            })
+        .iter()
+        .map(|(freq, antennas)| (*freq, antinodes(map, antennas, rule)))
+        .collect()
+}
+
+fn render(map: &Map, antinodes: &HashSet<Pos>) -> String {
+    let antennas: HashMap<Pos, char> = map
+        .antennas
+        .iter()
+        .flat_map(|(freq, antennas)| antennas.iter().map(|pos| (*pos, *freq)))
+        .collect();
+
+    (0..map.size.0 as i64)
+        .map(|l| {
+            (0..map.size.1 as i64)
+                .map(|i| {
+                    (0..map.size.2 as i64)
+                        .map(|j| match antennas.get(&(l, i, j)) {
+                            Some(freq) => *freq,
+                            None if antinodes.contains(&(l, i, j)) => '#',
+                            None => '.',
+                        })
+                        .collect::<String>()
+                })
+                .join("\n")
         })
-        .filter(|(i, j)| (0..map.size.0 as i64).contains(i) && (0..map.size.1 as i64).contains(j))
-        .unique()
-        .count()
-        .to_string()
+        .join("\n\n")
 }
 
-pub fn part2(input: &str) -> String {
+fn report(input: &str, rule: Rule) -> String {
     let map = parse(input);
+    let by_freq = antinodes_by_freq(&map, rule);
+    let all_antinodes: HashSet<Pos> = by_freq.values().flatten().copied().collect();
 
-    map.antennas
-        .values()
-        .flat_map(|freq_antennas| {
-            freq_antennas.iter().permutations(2).flat_map(|pair| {
-                let ((i1, j1), (i2, j2)) = (pair[0], pair[1]);
-                let (di, dj) = (i2 - i1, j2 - j1);
-                (0..)
-                    .map(move |n| (i2 + n * di, j2 + n * dj))
-                    .take_while(|(i, j)| {
-                        (0..map.size.0 as i64).contains(i) && (0..map.size.1 as i64).contains(j)
-                    })
+    let mut lines = vec![render(&map, &all_antinodes), String::new()];
+    lines.push(format!(
+        "{:<9} {:>8} {:>9} {:>8}",
+        "frequency", "antennas", "antinodes", "overlaps"
+    ));
+    by_freq.iter().for_each(|(freq, antinodes)| {
+        let overlaps = antinodes
+            .iter()
+            .filter(|pos| {
+                by_freq
+                    .iter()
+                    .any(|(other, others)| other != freq && others.contains(pos))
             })
-        })
+            .count();
+        lines.push(format!(
+            "{:<9} {:>8} {:>9} {:>8}",
+            freq,
+            map.antennas[freq].len(),
+            antinodes.len(),
+            overlaps
+        ));
+    });
+    lines.push(format!("total unique antinodes: {}", all_antinodes.len()));
+
+    lines.join("\n") + "\n"
+}
+
+fn csv(input: &str, rule: Rule) -> String {
+    let map = parse(input);
+    let layered = map.size.0 > 1;
+    let header = if layered {
+        "frequency,layer,row,col"
+    } else {
+        "frequency,row,col"
+    };
+
+    iter::once(header.to_string())
+        .chain(
+            antinodes_by_freq(&map, rule)
+                .into_iter()
+                .flat_map(|(freq, antinodes)| {
+                    antinodes
+                        .into_iter()
+                        .sorted()
+                        .map(move |(l, i, j)| match layered {
+                            true => format!("{freq},{l},{i},{j}"),
+                            false => format!("{freq},{i},{j}"),
+                        })
+                }),
+        )
+        .join("\n")
+        + "\n"
+}
+
+fn count(input: &str, rule: Rule) -> usize {
+    let map = parse(input);
+
+    antinodes_by_freq(&map, rule)
+        .into_values()
+        .flatten()
         .unique()
         .count()
-        .to_string()
+}
+
+pub fn command(input: &str, args: &[&str]) -> Option<String> {
+    let (name, rule) = match args {
+        [name] | [name, "part", "1"] => (*name, Rule::Ratio(2)),
+        [name, "part", "2"] => (*name, Rule::Resonant),
+        [name, "ratio", k] => match k.parse() {
+            Ok(k) if k >= 2 => (*name, Rule::Ratio(k)),
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    match name {
+        "report" => Some(report(input, rule)),
+        "csv" => Some(csv(input, rule)),
+        "count" => Some(format!("{}\n", count(input, rule))),
+        _ => None,
+    }
+}
+
+pub fn part1(input: &str) -> String {
+    count(input, Rule::Ratio(2)).to_string()
+}
+
+pub fn part2(input: &str) -> String {
+    count(input, Rule::Resonant).to_string()
 }
 
 #[cfg(test)]
@@ -108,4 +259,128 @@ mod tests {
 
         assert_eq!(part2(input), "34");
     }
+
+    #[test]
+    fn test_render() {
+        let input = "
+        ..........
+        ..........
+        ..........
+        ....a.....
+        ..........
+        .....a....
+        ..........
+        ..........
+        ..........
+        ..........
+        ";
+        let map = parse(input);
+        let antinodes = antinodes_by_freq(&map, Rule::Ratio(2))
+            .remove(&'a')
+            .unwrap();
+
+        assert_eq!(
+            render(&map, &antinodes),
+            "
+        ..........
+        ...#......
+        ..........
+        ....a.....
+        ..........
+        .....a....
+        ..........
+        ......#...
+        ..........
+        .........."
+                .trim()
+                .lines()
+                .map(str::trim)
+                .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_csv() {
+        let input = "
+        ..........
+        ..........
+        ..........
+        ....a.....
+        ..........
+        .....a....
+        ..........
+        ..........
+        ..........
+        ..........
+        ";
+
+        assert_eq!(
+            csv(input, Rule::Ratio(2)),
+            "frequency,row,col\na,1,3\na,7,6\n"
+        );
+    }
+
+    #[test]
+    fn test_ratio() {
+        let input = "
+        ..........
+        ..........
+        ..........
+        ..........
+        ..a.......
+        ..........
+        ..........
+        ..a.......
+        ..........
+        ..........
+        ";
+
+        assert_eq!(count(input, Rule::Ratio(2)), 1);
+        assert_eq!(count(input, Rule::Ratio(3)), 0);
+        assert_eq!(count(input, Rule::Ratio(4)), 2);
+        assert_eq!(count(input, Rule::Ratio(5)), 0);
+    }
+
+    #[test]
+    fn test_layers() {
+        let input = "
+        ....
+        .a..
+        ....
+
+        ....
+        ....
+        ....
+
+        ....
+        ..a.
+        ....
+        ";
+
+        assert_eq!(count(input, Rule::Ratio(2)), 0);
+        assert_eq!(count(input, Rule::Resonant), 2);
+        assert_eq!(
+            csv(input, Rule::Resonant),
+            "frequency,layer,row,col\na,0,1,1\na,2,1,2\n"
+        );
+
+        let input = "
+        a...
+        ....
+        ....
+
+        ....
+        .a..
+        ....
+
+        ....
+        ....
+        ....
+        ";
+
+        assert_eq!(
+            csv(input, Rule::Ratio(2)),
+            "frequency,layer,row,col\na,2,2,2\n"
+        );
+    }
 }
@@ -1,165 +1,501 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
 use std::iter;
 
-mod part1 {
-    pub struct Disk {
-        files: Vec<usize>,
-        free_spaces: Vec<usize>,
-    }
+use itertools::Itertools;
 
-    impl Disk {
-        pub fn iter(&self) -> DiskIterator {
-            DiskIterator {
-                disk: self,
-                files_iter: BlockRepeatIterator::new(0, &self.files),
-                files_iter_rev: RevBlockRepeatIterator::new(self.files.len() - 1, &self.files),
-                free_space_iter: BlockRepeatIterator::new(0, &self.free_spaces),
-            }
-        }
-    }
+type Id = usize;
+type Size = usize;
+type Pos = usize;
 
-    pub struct DiskIterator<'a> {
-        disk: &'a Disk,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Span {
+    pos: Pos,
+    size: Size,
+}
 
-        files_iter: BlockRepeatIterator,
-        files_iter_rev: RevBlockRepeatIterator,
-        free_space_iter: BlockRepeatIterator,
+impl Span {
+    fn end(&self) -> Pos {
+        self.pos + self.size
     }
+}
 
-    impl<'a> Iterator for DiskIterator<'a> {
-        type Item = usize;
+// Every file is a list of extents sorted by position. Files start out as a
+// single extent, and only block-level compaction splits them up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Disk {
+    len: usize,
+    files: Vec<Vec<Span>>,
+}
 
-        fn next(&mut self) -> Option<Self::Item> {
-            if self.files_iter.block_id == self.files_iter_rev.block_id
-                && self.files_iter.index as isize > self.files_iter_rev.index
-            {
-                return None;
-            }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placement {
+    First,
+    Best,
+    Worst,
+    Next,
+}
 
-            if let Some(id) = self.files_iter.next() {
-                return Some(id);
-            }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Order {
+    Descending,
+    Ascending,
+}
 
-            if let Some(_) = self.free_space_iter.next() {
-                loop {
-                    if let Some(id) = self.files_iter_rev.next() {
-                        return Some(id);
-                    }
-                    self.files_iter_rev = self.files_iter_rev.prev_block(&self.disk.files);
-                }
-            }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compaction {
+    Block,
+    WholeFile(Placement, Order),
+}
 
-            self.free_space_iter = self.free_space_iter.next_block(&self.disk.free_spaces);
-            self.files_iter = self.files_iter.next_block(&self.disk.files);
-            self.next()
+impl Placement {
+    fn name(&self) -> &'static str {
+        match self {
+            Placement::First => "first-fit",
+            Placement::Best => "best-fit",
+            Placement::Worst => "worst-fit",
+            Placement::Next => "next-fit",
         }
     }
+}
 
-    struct BlockRepeatIterator {
-        block_id: usize,
-        block_size: usize,
-        index: usize,
+impl Order {
+    fn name(&self) -> &'static str {
+        match self {
+            Order::Descending => "descending",
+            Order::Ascending => "ascending",
+        }
     }
+}
 
-    impl BlockRepeatIterator {
-        fn new(block_id: usize, blocks: &[usize]) -> Self {
-            Self {
-                block_id,
-                block_size: blocks[block_id],
-                index: 0,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Fragmentation {
+    free_spans: usize,
+    largest_free_span: Size,
+    wasted: Size,
+    free_tail: Size,
+}
+
+impl Disk {
+    fn free_spans(&self) -> Vec<Span> {
+        let mut free_spans = vec![];
+        let mut pos = 0;
+        for span in self.files.iter().flatten().sorted_by_key(|span| span.pos) {
+            if pos < span.pos {
+                free_spans.push(Span {
+                    pos,
+                    size: span.pos - pos,
+                });
             }
+            pos = span.end();
         }
+        if pos < self.len {
+            free_spans.push(Span {
+                pos,
+                size: self.len - pos,
+            });
+        }
+        free_spans
+    }
 
-        fn next_block(&self, blocks: &[usize]) -> Self {
-            Self::new(self.block_id + 1, blocks)
+    // Writes a block of file `id` at the free position `pos`, merging it into
+    // an adjacent extent of the same file when possible.
+    fn write_block(&mut self, id: Id, pos: Pos) {
+        let extents = &mut self.files[id];
+        let index = extents.partition_point(|span| span.pos < pos);
+        match index.checked_sub(1).map(|i| &mut extents[i]) {
+            Some(prev) if prev.end() == pos => prev.size += 1,
+            _ => extents.insert(index, Span { pos, size: 1 }),
         }
     }
 
-    impl Iterator for BlockRepeatIterator {
-        type Item = usize;
+    fn render(&self) -> String {
+        let mut blocks = vec!['.'; self.len];
+        self.files.iter().enumerate().for_each(|(id, extents)| {
+            let char = char::from_digit((id % 10) as u32, 10).unwrap();
+            extents
+                .iter()
+                .flat_map(|span| span.pos..span.end())
+                .for_each(|pos| blocks[pos] = char);
+        });
+        blocks.into_iter().collect()
+    }
+}
 
-        fn next(&mut self) -> Option<Self::Item> {
-            if self.index < self.block_size {
-                self.index += 1;
-                Some(self.block_id)
-            } else {
-                None
+fn parse(input: &str) -> Disk {
+    let mut files = vec![];
+    let mut pos = 0;
+    for (i, size) in input
+        .trim()
+        .chars()
+        .map(|c| c.to_digit(10).unwrap() as usize)
+        .enumerate()
+    {
+        if i % 2 == 0 {
+            files.push(match size {
+                0 => vec![],
+                _ => vec![Span { pos, size }],
+            });
+        }
+        pos += size;
+    }
+
+    Disk { len: pos, files }
+}
+
+fn checksum(disk: &Disk) -> usize {
+    disk.files
+        .iter()
+        .enumerate()
+        .flat_map(|(id, extents)| extents.iter().map(move |span| (id, span)))
+        .map(|(id, span)| id * (span.pos * span.size + span.size * (span.size - 1) / 2))
+        .sum()
+}
+
+// Moves single blocks from the end of the disk to the leftmost free block
+// until there are no gaps between files.
+fn compact_blocks(disk: &mut Disk, on_step: &mut impl FnMut(&Disk)) {
+    let free_spans = disk.free_spans();
+    let mut free_blocks = free_spans
+        .iter()
+        .flat_map(|span| span.pos..span.end())
+        .peekable();
+
+    for id in (0..disk.files.len()).rev() {
+        let Some(&file) = disk.files[id].last() else {
+            continue;
+        };
+
+        for pos in (file.pos..file.end()).rev() {
+            match free_blocks.next_if(|&free_pos| free_pos < pos) {
+                Some(free_pos) => {
+                    let last = disk.files[id].last_mut().unwrap();
+                    last.size -= 1;
+                    if last.size == 0 {
+                        disk.files[id].pop();
+                    }
+                    disk.write_block(id, free_pos);
+                    on_step(disk);
+                }
+                None => return,
             }
         }
     }
+}
+
+// Moves whole files, highest id first, to the leftmost free span that can
+// hold them, if there is one to the left of the file. Free spans are kept in
+// one min-heap of positions per span size, so finding the leftmost fitting
+// span only looks at the top of each heap that is at least as large as the file.
+fn compact_files_first_fit(disk: &mut Disk, on_step: &mut impl FnMut(&Disk)) {
+    let free_spans = disk.free_spans();
+    let max_size = free_spans.iter().map(|span| span.size).max().unwrap_or(0);
+    let mut free_heaps: Vec<BinaryHeap<Reverse<Pos>>> = vec![BinaryHeap::new(); max_size + 1];
+    free_spans
+        .iter()
+        .for_each(|span| free_heaps[span.size].push(Reverse(span.pos)));
 
-    struct RevBlockRepeatIterator {
-        block_id: usize,
-        index: isize,
+    for id in (0..disk.files.len()).rev() {
+        let file = match disk.files[id].as_slice() {
+            [] => continue,
+            [file] => *file,
+            _ => panic!("file {id} is fragmented"),
+        };
+
+        let leftmost_fit = free_heaps
+            .iter()
+            .enumerate()
+            .skip(file.size)
+            .filter_map(|(size, heap)| heap.peek().map(|Reverse(pos)| (*pos, size)))
+            .filter(|(pos, _)| *pos < file.pos)
+            .min();
+
+        if let Some((free_pos, free_size)) = leftmost_fit {
+            free_heaps[free_size].pop();
+            if free_size > file.size {
+                free_heaps[free_size - file.size].push(Reverse(free_pos + file.size));
+            }
+            disk.files[id][0].pos = free_pos;
+            on_step(disk);
+        }
     }
+}
 
-    impl RevBlockRepeatIterator {
-        fn new(block_id: usize, blocks: &[usize]) -> Self {
-            Self {
-                block_id,
-                index: blocks[block_id] as isize - 1,
+// Moves whole files in the given order to a free span on their left chosen by
+// the placement strategy. Unlike the first-fit fast path, the space a file
+// leaves behind is returned to the free list, because files moved in
+// ascending order can reuse it.
+fn compact_files(
+    disk: &mut Disk,
+    placement: Placement,
+    order: Order,
+    on_step: &mut impl FnMut(&Disk),
+) {
+    let mut free_spans: BTreeMap<Pos, Size> = disk
+        .free_spans()
+        .iter()
+        .map(|span| (span.pos, span.size))
+        .collect();
+    let mut next_fit_pos = 0;
+
+    let ids: Vec<Id> = match order {
+        Order::Descending => (0..disk.files.len()).rev().collect(),
+        Order::Ascending => (0..disk.files.len()).collect(),
+    };
+
+    for id in ids {
+        let file = match disk.files[id].as_slice() {
+            [] => continue,
+            [file] => *file,
+            _ => panic!("file {id} is fragmented"),
+        };
+
+        let mut fits = free_spans
+            .range(..file.pos)
+            .map(|(&pos, &size)| Span { pos, size })
+            .filter(|span| span.size >= file.size);
+        let free_span = match placement {
+            Placement::First => fits.next(),
+            Placement::Best => fits.min_by_key(|span| (span.size, span.pos)),
+            Placement::Worst => fits.min_by_key(|span| (Reverse(span.size), span.pos)),
+            Placement::Next => fits.min_by_key(|span| (span.pos < next_fit_pos, span.pos)),
+        };
+
+        if let Some(free_span) = free_span {
+            free_spans.remove(&free_span.pos);
+            if free_span.size > file.size {
+                free_spans.insert(free_span.pos + file.size, free_span.size - file.size);
             }
+            release(&mut free_spans, file);
+            disk.files[id][0].pos = free_span.pos;
+            next_fit_pos = free_span.pos + file.size;
+            on_step(disk);
         }
+    }
+}
+
+// Returns a span to the free list, merging it with its free neighbours.
+fn release(free_spans: &mut BTreeMap<Pos, Size>, mut span: Span) {
+    if let Some((&pos, &size)) = free_spans.range(..span.pos).next_back() {
+        if pos + size == span.pos {
+            free_spans.remove(&pos);
+            span = Span {
+                pos,
+                size: size + span.size,
+            };
+        }
+    }
+    if let Some(size) = free_spans.remove(&span.end()) {
+        span.size += size;
+    }
+    free_spans.insert(span.pos, span.size);
+}
 
-        fn prev_block(&self, blocks: &[usize]) -> Self {
-            Self::new(self.block_id - 1, blocks)
+fn compact(disk: &mut Disk, compaction: Compaction, mut on_step: impl FnMut(&Disk)) {
+    match compaction {
+        Compaction::Block => compact_blocks(disk, &mut on_step),
+        Compaction::WholeFile(Placement::First, Order::Descending) => {
+            compact_files_first_fit(disk, &mut on_step)
+        }
+        Compaction::WholeFile(placement, order) => {
+            compact_files(disk, placement, order, &mut on_step)
         }
     }
+}
 
-    impl Iterator for RevBlockRepeatIterator {
-        type Item = usize;
+// Free spans between files are fragmentation, while the free space after the
+// last file is one contiguous tail.
+fn fragmentation(disk: &Disk) -> Fragmentation {
+    let mut free_spans = disk.free_spans();
+    let free_tail = match free_spans.last() {
+        Some(span) if span.end() == disk.len => free_spans.pop().unwrap().size,
+        _ => 0,
+    };
+
+    Fragmentation {
+        free_spans: free_spans.len(),
+        largest_free_span: free_spans.iter().map(|span| span.size).max().unwrap_or(0),
+        wasted: free_spans.iter().map(|span| span.size).sum(),
+        free_tail,
+    }
+}
 
-        fn next(&mut self) -> Option<Self::Item> {
-            if self.index >= 0 {
-                self.index -= 1;
-                Some(self.block_id)
-            } else {
-                None
+fn parse_compaction<'a>(args: &[&'a str]) -> Option<(Compaction, Option<&'a str>)> {
+    let mut placement = None;
+    let mut order = Order::Descending;
+    let mut disk_map = None;
+    let mut args = args.iter();
+    while let Some(&arg) = args.next() {
+        match arg {
+            "part" => {
+                placement = match args.next() {
+                    Some(&"1") => None,
+                    Some(&"2") => Some(Placement::First),
+                    _ => return None,
+                }
             }
+            "block" => placement = None,
+            "first-fit" => placement = Some(Placement::First),
+            "best-fit" => placement = Some(Placement::Best),
+            "worst-fit" => placement = Some(Placement::Worst),
+            "next-fit" => placement = Some(Placement::Next),
+            "ascending" => order = Order::Ascending,
+            _ if arg.chars().all(|c| c.is_ascii_digit()) => disk_map = Some(arg),
+            _ => return None,
         }
     }
 
-    pub fn parse(input: &str) -> Disk {
-        Disk {
-            files: input
-                .trim()
-                .chars()
-                .step_by(2)
-                .map(|c| c.to_digit(10).unwrap() as usize)
-                .collect(),
-            free_spaces: input
-                .trim()
-                .chars()
-                .skip(1)
-                .step_by(2)
-                .map(|c| c.to_digit(10).unwrap() as usize)
-                .collect(),
+    let compaction = match (placement, order) {
+        (None, Order::Descending) => Compaction::Block,
+        (None, Order::Ascending) => return None,
+        (Some(placement), order) => Compaction::WholeFile(placement, order),
+    };
+    Some((compaction, disk_map))
+}
+
+fn report(input: &str) -> String {
+    let compactions = iter::once(Compaction::Block).chain(
+        [Order::Descending, Order::Ascending]
+            .into_iter()
+            .cartesian_product([
+                Placement::First,
+                Placement::Best,
+                Placement::Worst,
+                Placement::Next,
+            ])
+            .map(|(order, placement)| Compaction::WholeFile(placement, order)),
+    );
+
+    iter::once(format!(
+        "{:<10} {:<10} {:>20} {:>10} {:>8} {:>8} {:>8}",
+        "placement", "order", "checksum", "free spans", "largest", "wasted", "tail"
+    ))
+    .chain(compactions.map(|compaction| {
+        let mut disk = parse(input);
+        compact(&mut disk, compaction, |_| {});
+        let metrics = fragmentation(&disk);
+        let (placement, order) = match compaction {
+            Compaction::Block => ("block", Order::Descending.name()),
+            Compaction::WholeFile(placement, order) => (placement.name(), order.name()),
+        };
+        format!(
+            "{:<10} {:<10} {:>20} {:>10} {:>8} {:>8} {:>8}",
+            placement,
+            order,
+            checksum(&disk),
+            metrics.free_spans,
+            metrics.largest_free_span,
+            metrics.wasted,
+            metrics.free_tail
+        )
+    }))
+    .join("\n")
+        + "\n"
+}
+
+pub fn command(input: &str, args: &[&str]) -> Option<String> {
+    match args {
+        ["render", args @ ..] => {
+            let (compaction, disk_map) = parse_compaction(args)?;
+            let mut disk = parse(disk_map.unwrap_or(input));
+            let mut frames = vec![disk.render()];
+            compact(&mut disk, compaction, |disk| frames.push(disk.render()));
+            frames.push(format!("checksum: {}", checksum(&disk)));
+            let metrics = fragmentation(&disk);
+            frames.push(format!(
+                "free spans: {}, largest free span: {}, wasted: {}, free tail: {}",
+                metrics.free_spans, metrics.largest_free_span, metrics.wasted, metrics.free_tail
+            ));
+            Some(frames.join("\n") + "\n")
         }
+        ["report"] => Some(report(input)),
+        ["report", disk_map] => Some(report(disk_map)),
+        _ => None,
     }
 }
 
 pub fn part1(input: &str) -> String {
-    let disk = part1::parse(input);
-    disk.iter()
-        .enumerate()
-        .map(|(i, id)| i * id)
-        .sum::<usize>()
-        .to_string()
+    let mut disk = parse(input);
+    compact(&mut disk, Compaction::Block, |_| {});
+    checksum(&disk).to_string()
+}
+
+pub fn part2(input: &str) -> String {
+    let mut disk = parse(input);
+    compact(
+        &mut disk,
+        Compaction::WholeFile(Placement::First, Order::Descending),
+        |_| {},
+    );
+    checksum(&disk).to_string()
 }
 
-mod part2 {
+#[cfg(test)]
+mod tests {
     use std::collections::HashMap;
 
-    type Id = usize;
-    type Size = usize;
-    type Pos = usize;
+    use rand::Rng;
+
+    use super::*;
+
+    #[test]
+    fn test_part1() {
+        let input = "2333133121414131402";
+        assert_eq!(part1(input), "1928");
+    }
 
-    #[derive(Debug)]
-    pub struct Disk {
-        pub files: HashMap<Id, (Size, Pos)>,
-        pub free_space_map: HashMap<Size, Vec<(Size, Pos)>>,
+    #[test]
+    fn test_part2() {
+        let input = "2333133121414131402";
+        assert_eq!(part2(input), "2858");
     }
 
-    pub fn parse(input: &str) -> Disk {
+    #[test]
+    fn test_render_blocks() {
+        let mut disk = parse("12345");
+        let mut frames = vec![disk.render()];
+        compact(&mut disk, Compaction::Block, |disk| {
+            frames.push(disk.render())
+        });
+
+        assert_eq!(
+            frames,
+            vec![
+                "0..111....22222",
+                "02.111....2222.",
+                "022111....222..",
+                "0221112...22...",
+                "02211122..2....",
+                "022111222......",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_files() {
+        let mut disk = parse("2333133121414131402");
+        let mut frames = vec![disk.render()];
+        compact(
+            &mut disk,
+            Compaction::WholeFile(Placement::First, Order::Descending),
+            |disk| frames.push(disk.render()),
+        );
+
+        assert_eq!(
+            frames,
+            vec![
+                "00...111...2...333.44.5555.6666.777.888899",
+                "0099.111...2...333.44.5555.6666.777.8888..",
+                "0099.1117772...333.44.5555.6666.....8888..",
+                "0099.111777244.333....5555.6666.....8888..",
+                "00992111777.44.333....5555.6666.....8888..",
+            ]
+        );
+    }
+
+    // The original whole-file defragmenter, kept as a reference: free spans are
+    // listed under every size they can hold, in order of position.
+    fn defragment_reference(input: &str) -> HashMap<Id, (Size, Pos)> {
         let mut files: HashMap<Id, (Size, Pos)> = HashMap::new();
         let mut free_space_map: HashMap<Size, Vec<(Size, Pos)>> = HashMap::new();
 
@@ -171,84 +507,114 @@ mod part2 {
             .enumerate()
         {
             if i % 2 == 0 {
-                let id = i / 2;
-                files.insert(id, (size, pos));
-                pos += size;
+                files.insert(i / 2, (size, pos));
             } else {
                 (1..=size).for_each(|s| free_space_map.entry(s).or_default().push((size, pos)));
-                pos += size;
             }
+            pos += size;
         }
 
-        Disk {
-            files,
-            free_space_map,
-        }
-    }
-
-    pub fn defragment(mut disk: Disk) -> HashMap<Id, (Size, Pos)> {
-        for id in (0..=*disk.files.keys().max().unwrap()).rev() {
-            let &(size, pos) = disk.files.get(&id).unwrap();
-            if let Some(positions) = disk.free_space_map.get_mut(&size) {
-                if let Some(&(free_size, free_pos)) =
-                    positions.first().filter(|&&(_, free_pos)| free_pos < pos)
-                {
-                    disk.files.insert(id, (size, free_pos));
-
-                    (1..=free_size).for_each(|s| {
-                        disk.free_space_map.entry(s).and_modify(|positions| {
-                            let new_free_size = free_size - size;
-                            if s <= new_free_size {
-                                positions
-                                    .iter_mut()
-                                    .find(|x| *x == &(free_size, free_pos))
-                                    .map(|free_space| {
-                                        *free_space = (new_free_size, free_pos + size);
-                                    });
-                            } else {
-                                positions.remove(
-                                    positions
-                                        .iter()
-                                        .position(|x| x == &(free_size, free_pos))
-                                        .unwrap(),
-                                );
-                            };
-                        });
-                    });
-                };
+        for id in (0..=*files.keys().max().unwrap()).rev() {
+            let (size, pos) = files[&id];
+            let Some(&(free_size, free_pos)) = free_space_map
+                .get(&size)
+                .and_then(|positions| positions.first())
+                .filter(|&&(_, free_pos)| free_pos < pos)
+            else {
+                continue;
             };
+            files.insert(id, (size, free_pos));
+
+            let new_free_size = free_size - size;
+            for s in 1..=free_size {
+                let positions = free_space_map.get_mut(&s).unwrap();
+                let index = positions
+                    .iter()
+                    .position(|x| *x == (free_size, free_pos))
+                    .unwrap();
+                if s <= new_free_size {
+                    positions[index] = (new_free_size, free_pos + size);
+                } else {
+                    positions.remove(index);
+                }
+            }
         }
 
-        disk.files
+        files.retain(|_, (size, _)| *size > 0);
+        files
     }
-}
-
-pub fn part2(_input: &str) -> String {
-    let disk = part2::parse(_input);
-
-    let files = part2::defragment(disk);
 
-    files
-        .iter()
-        .flat_map(|(&id, &(size, pos))| iter::repeat(id).zip(pos..pos + size))
-        .map(|(id, pos)| id * pos)
-        .sum::<usize>()
-        .to_string()
-}
+    #[test]
+    fn test_compact_files_random() {
+        let mut rng = rand::rng();
+
+        for _ in 0..500 {
+            let len = rng.random_range(1..200);
+            // The reference keeps the free spans on either side of an empty
+            // file apart, so every file gets at least one block.
+            let input: String = (0..len)
+                .map(|i| {
+                    let min_size = if i % 2 == 0 { 1 } else { 0 };
+                    char::from_digit(rng.random_range(min_size..10), 10).unwrap()
+                })
+                .collect();
+
+            let expected = defragment_reference(&input);
+            let files = |disk: &Disk| -> HashMap<Id, (Size, Pos)> {
+                disk.files
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(id, extents)| extents.first().map(|span| (id, span)))
+                    .map(|(id, span)| (id, (span.size, span.pos)))
+                    .collect()
+            };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+            let mut disk = parse(&input);
+            compact(
+                &mut disk,
+                Compaction::WholeFile(Placement::First, Order::Descending),
+                |_| {},
+            );
+            assert_eq!(files(&disk), expected, "disk map {input}");
+
+            let mut disk = parse(&input);
+            compact_files(&mut disk, Placement::First, Order::Descending, &mut |_| {});
+            assert_eq!(files(&disk), expected, "disk map {input}");
+        }
+    }
 
     #[test]
-    fn test_part1() {
-        let input = "2333133121414131402";
-        assert_eq!(part1(input), "1928");
+    fn test_placements() {
+        let compacted = |placement, order| {
+            let mut disk = parse("13111");
+            compact(&mut disk, Compaction::WholeFile(placement, order), |_| {});
+            disk.render()
+        };
+
+        assert_eq!(compacted(Placement::First, Order::Descending), "021....");
+        assert_eq!(compacted(Placement::Best, Order::Descending), "01...2.");
+        assert_eq!(compacted(Placement::Worst, Order::Descending), "021....");
+        assert_eq!(compacted(Placement::Next, Order::Descending), "021....");
+        assert_eq!(compacted(Placement::First, Order::Ascending), "012....");
     }
 
     #[test]
-    fn test_part2() {
-        let input = "2333133121414131402";
-        assert_eq!(part2(input), "2858");
+    fn test_fragmentation() {
+        let mut disk = parse("2333133121414131402");
+        compact(
+            &mut disk,
+            Compaction::WholeFile(Placement::First, Order::Descending),
+            |_| {},
+        );
+
+        assert_eq!(
+            fragmentation(&disk),
+            Fragmentation {
+                free_spans: 5,
+                largest_free_span: 5,
+                wasted: 12,
+                free_tail: 2,
+            }
+        );
     }
 }
@@ -1,64 +1,494 @@
+use std::collections::{HashSet, VecDeque};
+use std::{fmt, iter};
+
 use itertools::Itertools;
-use std::collections::HashMap;
 
+type Pos = (i64, i64);
+
+const DIRS: [Pos; 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+const ALL_DIRS: [Pos; 8] = [
+    (0, 1),
+    (1, 0),
+    (0, -1),
+    (-1, 0),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
+// When steps may descend, the number of trails can grow exponentially with the
+// size of the map, so only this many are followed from each trailhead unless
+// a limit is given.
+const TRAIL_LIMIT: usize = 1000;
+
+// A step may climb by up to `max_climb` and, when `max_descent` is not zero,
+// descend by up to `max_descent`. The puzzle's rule climbs exactly one.
+#[derive(Debug, Clone, Copy)]
+struct StepRule {
+    max_climb: i64,
+    max_descent: i64,
+    diagonal: bool,
+}
+
+impl StepRule {
+    fn dirs(&self) -> &'static [Pos] {
+        match self.diagonal {
+            true => &ALL_DIRS,
+            false => &DIRS,
+        }
+    }
+}
+
+impl Default for StepRule {
+    fn default() -> Self {
+        StepRule {
+            max_climb: 1,
+            max_descent: 0,
+            diagonal: false,
+        }
+    }
+}
+
+// Cells that are not digits are impassable and have no height.
 struct Map {
     size: (i64, i64),
-    grid: HashMap<(i64, i64), i64>,
-    trailheads: Vec<(i64, i64)>,
+    grid: Vec<Option<i64>>,
+    trailheads: Vec<Pos>,
+    rule: StepRule,
 }
 
-const DIRS: [(i64, i64); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+// A trail that only climbs reaches height 9 within 9 steps, so every summit it
+// reaches is at most 9 rows and 9 columns away from the trailhead. Summits
+// whose rows and columns agree modulo 19 are therefore never reachable from
+// the same cell, and a bitset over the 19 * 19 residue classes identifies
+// every summit reachable from a cell.
+const SUMMIT_CLASSES: i64 = 19;
+
+const SUMMIT_WORDS: usize = ((SUMMIT_CLASSES * SUMMIT_CLASSES) as usize).div_ceil(64);
+
+type Summits = [u64; SUMMIT_WORDS];
+
+impl Map {
+    fn index(&self, (i, j): Pos) -> Option<usize> {
+        ((0..self.size.0).contains(&i) && (0..self.size.1).contains(&j))
+            .then(|| (i * self.size.1 + j) as usize)
+    }
+
+    fn height(&self, pos: Pos) -> Option<i64> {
+        self.index(pos).and_then(|index| self.grid[index])
+    }
+
+    fn positions(&self) -> impl Iterator<Item = Pos> + '_ {
+        (0..self.size.0).flat_map(move |i| (0..self.size.1).map(move |j| (i, j)))
+    }
+
+    // Trails end at the first summit they reach.
+    fn can_step(&self, pos: Pos, next_pos: Pos) -> bool {
+        let height = self.height(pos).unwrap();
+        match self.height(next_pos) {
+            Some(next_height) if height != 9 => {
+                (1..=self.rule.max_climb).contains(&(next_height - height))
+                    || (1..=self.rule.max_descent).contains(&(height - next_height))
+            }
+            _ => false,
+        }
+    }
+
+    fn next_steps(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.rule
+            .dirs()
+            .iter()
+            .map(move |(di, dj)| (pos.0 + di, pos.1 + dj))
+            .filter(move |next_pos| self.can_step(pos, *next_pos))
+    }
+
+    fn is_acyclic(&self) -> bool {
+        self.rule.max_descent == 0
+    }
+}
 
 fn parse(input: &str) -> Map {
-    let lines: Vec<&str> = input.trim().lines().collect();
+    let lines: Vec<&str> = input.trim().lines().map(str::trim).collect();
     let rows = lines.len();
-    let cols = lines.get(0).unwrap().trim().len();
+    let cols = lines.first().unwrap().len();
 
-    let grid: HashMap<(i64, i64), i64> = lines
+    let grid: Vec<Option<i64>> = lines
         .iter()
-        .enumerate()
-        .flat_map(|(i, line)| {
-            line.trim()
-                .char_indices()
-                .map(move |(j, char)| ((i as i64, j as i64), char.to_digit(10).unwrap() as i64))
-        })
+        .flat_map(|line| line.chars().map(|char| char.to_digit(10).map(|d| d as i64)))
         .collect();
 
-    let trailheads = grid
-        .iter()
-        .filter_map(|(k, v)| if *v == 0 { Some(*k) } else { None })
-        .collect();
-
-    Map {
+    let mut map = Map {
         size: (rows as i64, cols as i64),
         grid,
-        trailheads,
+        trailheads: vec![],
+        rule: StepRule::default(),
+    };
+    map.trailheads = map
+        .positions()
+        .filter(|pos| map.height(*pos) == Some(0))
+        .collect();
+    map
+}
+
+struct Trails {
+    summits: Vec<Summits>,
+    ratings: Vec<usize>,
+}
+
+impl Trails {
+    fn score(&self, index: usize) -> usize {
+        self.summits[index]
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+}
+
+// Computes, for every cell, the set of summits reachable from it and the
+// number of distinct trails to them, going down one height level at a time.
+// Only possible when steps never descend.
+fn trails(map: &Map) -> Trails {
+    assert!(map.is_acyclic());
+
+    let mut levels: Vec<Vec<Pos>> = vec![vec![]; 10];
+    map.positions().for_each(|pos| {
+        if let Some(height @ 0..=9) = map.height(pos) {
+            levels[height as usize].push(pos)
+        }
+    });
+
+    let mut trails = Trails {
+        summits: vec![[0; SUMMIT_WORDS]; map.grid.len()],
+        ratings: vec![0; map.grid.len()],
+    };
+
+    for &(i, j) in &levels[9] {
+        let index = map.index((i, j)).unwrap();
+        let class = (i % SUMMIT_CLASSES * SUMMIT_CLASSES + j % SUMMIT_CLASSES) as usize;
+        trails.summits[index][class / 64] |= 1 << (class % 64);
+        trails.ratings[index] = 1;
+    }
+
+    for level in levels[..9].iter().rev() {
+        for &pos in level {
+            let index = map.index(pos).unwrap();
+            for next_pos in map.next_steps(pos) {
+                let next_index = map.index(next_pos).unwrap();
+                for word in 0..SUMMIT_WORDS {
+                    trails.summits[index][word] |= trails.summits[next_index][word];
+                }
+                trails.ratings[index] += trails.ratings[next_index];
+            }
+        }
+    }
+
+    trails
+}
+
+// Marks the cells from which some summit can be reached.
+fn reaches_summit(map: &Map) -> Vec<bool> {
+    let mut prev_steps: Vec<Vec<Pos>> = vec![vec![]; map.grid.len()];
+    map.positions()
+        .filter(|pos| map.height(*pos).is_some())
+        .for_each(|pos| {
+            map.next_steps(pos)
+                .for_each(|next_pos| prev_steps[map.index(next_pos).unwrap()].push(pos))
+        });
+
+    let mut reaches = vec![false; map.grid.len()];
+    let mut queue: VecDeque<Pos> = map
+        .positions()
+        .filter(|pos| map.height(*pos) == Some(9))
+        .collect();
+    while let Some(pos) = queue.pop_front() {
+        let index = map.index(pos).unwrap();
+        if !reaches[index] {
+            reaches[index] = true;
+            queue.extend(&prev_steps[index]);
+        }
+    }
+    reaches
+}
+
+// Visits up to `limit` distinct trails from a trailhead, and tells whether
+// there were more. A trail never visits a cell twice, which only matters when
+// steps may descend.
+fn walk_trails(
+    map: &Map,
+    reaches: &[bool],
+    trailhead: Pos,
+    limit: usize,
+    mut visit: impl FnMut(&[Pos]),
+) -> bool {
+    let mut found = 0;
+    let mut on_trail = vec![false; map.grid.len()];
+    on_trail[map.index(trailhead).unwrap()] = true;
+    let mut trail = vec![trailhead];
+    // The next direction to try from each cell of the trail.
+    let mut branches = vec![0];
+    while let Some(branch) = branches.last_mut() {
+        let pos = *trail.last().unwrap();
+        let dirs = map.rule.dirs();
+        if map.height(pos) == Some(9) || *branch == dirs.len() {
+            if map.height(pos) == Some(9) {
+                if found == limit {
+                    return true;
+                }
+                visit(&trail);
+                found += 1;
+            }
+            on_trail[map.index(pos).unwrap()] = false;
+            trail.pop();
+            branches.pop();
+            continue;
+        }
+
+        let (di, dj) = dirs[*branch];
+        *branch += 1;
+        let next_pos = (pos.0 + di, pos.1 + dj);
+        if map.can_step(pos, next_pos) {
+            let next_index = map.index(next_pos).unwrap();
+            if reaches[next_index] && !on_trail[next_index] {
+                on_trail[next_index] = true;
+                trail.push(next_pos);
+                branches.push(0);
+            }
+        }
     }
+    false
 }
 
-fn hike(map: &Map, pos: (i64, i64)) -> Vec<(i64, i64)> {
-    match map.grid.get(&pos) {
-        None => vec![],
-        Some(height) if *height == 9 => vec![pos],
-        Some(height) => DIRS
+// Lists up to `limit` distinct trails from a trailhead.
+fn trails_from(map: &Map, trailhead: Pos, limit: usize) -> Vec<Vec<Pos>> {
+    let mut found = vec![];
+    walk_trails(map, &reaches_summit(map), trailhead, limit, |trail| {
+        found.push(trail.to_vec())
+    });
+    found
+}
+
+// The number of distinct trails from a trailhead, which is only a lower bound
+// when the search stopped at its limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rating {
+    trails: usize,
+    truncated: bool,
+}
+
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.truncated {
+            true => write!(f, "{}+", self.trails),
+            false => write!(f, "{}", self.trails),
+        }
+    }
+}
+
+// The score and rating of every trailhead. When steps may descend, trails
+// can wind around, and rating them means listing every trail that never
+// visits a cell twice, up to `limit` trails per trailhead.
+fn trailhead_stats(map: &Map, limit: usize) -> Vec<(usize, Rating)> {
+    if map.is_acyclic() {
+        let trails = trails(map);
+        return map
+            .trailheads
             .iter()
-            .map(|(di, dj)| (pos.0 + di, pos.1 + dj))
-            .filter(|next_pos| {
-                (0..map.size.0).contains(&next_pos.0) && (0..map.size.1).contains(&next_pos.1)
+            .map(|trailhead| map.index(*trailhead).unwrap())
+            .map(|index| {
+                let rating = Rating {
+                    trails: trails.ratings[index],
+                    truncated: false,
+                };
+                (trails.score(index), rating)
             })
-            .filter(|next_pos| height + 1 == *map.grid.get(next_pos).unwrap())
-            .flat_map(|next_pos| hike(map, next_pos))
-            .collect(),
+            .collect();
+    }
+
+    let reaches = reaches_summit(map);
+    map.trailheads
+        .iter()
+        .map(|trailhead| {
+            let mut visited = HashSet::from([*trailhead]);
+            let mut queue = VecDeque::from([*trailhead]);
+            while let Some(pos) = queue.pop_front() {
+                queue.extend(map.next_steps(pos).filter(|pos| visited.insert(*pos)));
+            }
+            let score = visited
+                .iter()
+                .filter(|pos| map.height(**pos) == Some(9))
+                .count();
+
+            let mut trails = 0;
+            let truncated = walk_trails(map, &reaches, *trailhead, limit, |_| trails += 1);
+            (score, Rating { trails, truncated })
+        })
+        .collect()
+}
+
+// The number of distinct trails through a cell is the number of ways up to it
+// from any trailhead times the number of ways on from it to any summit. When
+// steps may descend, up to `limit` trails per trailhead are listed and counted
+// instead, and the flag tells whether any were left out.
+fn heat_map(map: &Map, limit: usize) -> (Vec<usize>, bool) {
+    if !map.is_acyclic() {
+        let reaches = reaches_summit(map);
+        let mut heat_map = vec![0; map.grid.len()];
+        let mut truncated = false;
+        for trailhead in &map.trailheads {
+            truncated |= walk_trails(map, &reaches, *trailhead, limit, |trail| {
+                trail
+                    .iter()
+                    .for_each(|pos| heat_map[map.index(*pos).unwrap()] += 1)
+            });
+        }
+        return (heat_map, truncated);
+    }
+
+    let trails = trails(map);
+    let mut ascents = vec![0; map.grid.len()];
+    map.trailheads
+        .iter()
+        .for_each(|trailhead| ascents[map.index(*trailhead).unwrap()] = 1);
+
+    for height in 0..9 {
+        for pos in map
+            .positions()
+            .filter(|pos| map.height(*pos) == Some(height))
+        {
+            let index = map.index(pos).unwrap();
+            for next_pos in map.next_steps(pos) {
+                ascents[map.index(next_pos).unwrap()] += ascents[index];
+            }
+        }
+    }
+
+    let heat_map = ascents
+        .iter()
+        .zip(&trails.ratings)
+        .map(|(ascents, ratings)| ascents * ratings)
+        .collect();
+    (heat_map, false)
+}
+
+fn render_trail(map: &Map, trail: &[Pos]) -> String {
+    (0..map.size.0)
+        .map(|i| {
+            (0..map.size.1)
+                .map(|j| match map.height((i, j)) {
+                    Some(height) if trail.contains(&(i, j)) => height.to_string(),
+                    _ => ".".to_string(),
+                })
+                .collect::<String>()
+        })
+        .join("\n")
+}
+
+fn render_heat_map(map: &Map, heat_map: &[usize]) -> String {
+    let width = heat_map.iter().max().unwrap_or(&0).to_string().len();
+    heat_map
+        .chunks(map.size.1 as usize)
+        .map(|row| row.iter().map(|count| format!("{count:>width$}")).join(" "))
+        .join("\n")
+}
+
+fn csv(map: &Map, limit: usize) -> String {
+    iter::once("row,col,score,rating".to_string())
+        .chain(
+            map.trailheads
+                .iter()
+                .zip(trailhead_stats(map, limit))
+                .map(|((i, j), (score, rating))| format!("{i},{j},{score},{rating}")),
+        )
+        .join("\n")
+}
+
+// Splits the step rule options `climb <k>`, `descent <k>` and `diagonal` off
+// the end of the arguments.
+fn parse_rule<'a, 'b>(mut args: &'a [&'b str]) -> Option<(StepRule, &'a [&'b str])> {
+    let mut rule = StepRule::default();
+    loop {
+        args = match args {
+            [rest @ .., "climb", k] => {
+                rule.max_climb = k.parse().ok()?;
+                rest
+            }
+            [rest @ .., "descent", k] => {
+                rule.max_descent = k.parse().ok()?;
+                rest
+            }
+            [rest @ .., "diagonal"] => {
+                rule.diagonal = true;
+                rest
+            }
+            _ => return Some((rule, args)),
+        };
     }
 }
 
+// Parses the optional trail limit, which defaults to `TRAIL_LIMIT` when steps
+// may descend.
+fn parse_limit(map: &Map, args: &[&str]) -> Option<usize> {
+    match args {
+        [] if map.is_acyclic() => Some(usize::MAX),
+        [] => Some(TRAIL_LIMIT),
+        [limit] => limit.parse().ok(),
+        _ => None,
+    }
+}
+
+pub fn command(input: &str, args: &[&str]) -> Option<String> {
+    let (rule, args) = parse_rule(args)?;
+    let mut map = parse(input);
+    map.rule = rule;
+
+    let output = match args {
+        ["trails", i, j, rest @ ..] => {
+            let trailhead = (i.parse().ok()?, j.parse().ok()?);
+            if map.height(trailhead) != Some(0) {
+                return None;
+            }
+            trails_from(&map, trailhead, parse_limit(&map, rest)?)
+                .iter()
+                .enumerate()
+                .map(|(n, trail)| {
+                    let steps = trail.iter().map(|(i, j)| format!("({i},{j})")).join(" -> ");
+                    format!("trail {}: {steps}\n{}", n + 1, render_trail(&map, trail))
+                })
+                .join("\n\n")
+        }
+        ["heatmap", rest @ ..] => {
+            let limit = parse_limit(&map, rest)?;
+            let (heat_map, truncated) = heat_map(&map, limit);
+            let mut output = render_heat_map(&map, &heat_map);
+            if truncated {
+                output += &format!("\nonly the first {limit} trails from each trailhead counted");
+            }
+            output
+        }
+        ["csv", rest @ ..] => csv(&map, parse_limit(&map, rest)?),
+        ["count", rest @ ..] => {
+            let (scores, ratings): (Vec<usize>, Vec<Rating>) =
+                trailhead_stats(&map, parse_limit(&map, rest)?)
+                    .into_iter()
+                    .unzip();
+            let rating = Rating {
+                trails: ratings.iter().map(|rating| rating.trails).sum(),
+                truncated: ratings.iter().any(|rating| rating.truncated),
+            };
+            format!("score: {}\nrating: {rating}", scores.iter().sum::<usize>())
+        }
+        _ => return None,
+    };
+    Some(output + "\n")
+}
+
 pub fn part1(input: &str) -> String {
     let map = parse(input);
 
-    map.trailheads
+    trailhead_stats(&map, usize::MAX)
         .iter()
-        .map(|trailhead| hike(&map, *trailhead))
-        .map(|summits| summits.iter().unique().count())
+        .map(|(score, _)| score)
         .sum::<usize>()
         .to_string()
 }
@@ -66,15 +496,17 @@ pub fn part1(input: &str) -> String {
 pub fn part2(input: &str) -> String {
     let map = parse(input);
 
-    map.trailheads
+    trailhead_stats(&map, usize::MAX)
         .iter()
-        .map(|trailhead| hike(&map, *trailhead).len())
+        .map(|(_, rating)| rating.trails)
         .sum::<usize>()
         .to_string()
 }
 
 #[cfg(test)]
 mod tests {
+    use rand::Rng;
+
     use super::*;
 
     #[test]
@@ -106,4 +538,207 @@ mod tests {
         ";
         assert_eq!(part2(input), "81");
     }
+
+    fn hike(map: &Map, pos: Pos) -> Vec<Pos> {
+        match map.height(pos) {
+            Some(9) => vec![pos],
+            _ => map
+                .next_steps(pos)
+                .flat_map(|next_pos| hike(map, next_pos))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_trails_random() {
+        let mut rng = rand::rng();
+
+        for _ in 0..100 {
+            let (rows, cols) = (rng.random_range(1..40), rng.random_range(1..40));
+            let input = (0..rows)
+                .map(|i| {
+                    (0..cols)
+                        .map(|j| (i + j + rng.random_range(0..2)) % 10)
+                        .join("")
+                })
+                .join("\n");
+            let map = parse(&input);
+
+            let score = map
+                .trailheads
+                .iter()
+                .map(|trailhead| hike(&map, *trailhead).iter().unique().count())
+                .sum::<usize>();
+            let rating = map
+                .trailheads
+                .iter()
+                .map(|trailhead| hike(&map, *trailhead).len())
+                .sum::<usize>();
+
+            assert_eq!(part1(&input), score.to_string(), "map\n{input}");
+            assert_eq!(part2(&input), rating.to_string(), "map\n{input}");
+        }
+    }
+
+    #[test]
+    fn test_trails_from() {
+        let input = "
+        0123
+        1234
+        8765
+        9876
+        ";
+        let map = parse(input);
+        let found = trails_from(&map, (0, 0), usize::MAX);
+
+        assert_eq!(found.len(), 16);
+        assert!(found.iter().all_unique());
+        assert_eq!(trails_from(&map, (0, 0), 3).len(), 3);
+        assert_eq!(
+            render_trail(&map, &found[0]),
+            "
+        0123
+        ...4
+        ...5
+        9876"
+                .trim()
+                .lines()
+                .map(str::trim)
+                .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_heat_map() {
+        let input = "
+        89010123
+        78121874
+        87430965
+        96549874
+        45678903
+        32019012
+        01329801
+        10456732
+        ";
+        let map = parse(input);
+        let all_trails: Vec<Vec<Pos>> = map
+            .trailheads
+            .iter()
+            .flat_map(|trailhead| trails_from(&map, *trailhead, usize::MAX))
+            .collect();
+
+        assert_eq!(all_trails.len(), 81);
+        assert_eq!(
+            heat_map(&map, usize::MAX).0,
+            map.positions()
+                .map(|pos| all_trails
+                    .iter()
+                    .filter(|trail| trail.contains(&pos))
+                    .count())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            csv(&map, usize::MAX).lines().take(3).join("\n"),
+            "row,col,score,rating\n0,2,5,20\n0,4,6,24"
+        );
+    }
+
+    #[test]
+    fn test_impassable() {
+        let input = "
+        ...0...
+        ...1...
+        ...2...
+        6543456
+        7.....7
+        8.....8
+        9.....9
+        ";
+        assert_eq!(part1(input), "2");
+
+        let input = "
+        ..90..9
+        ...1.98
+        ...2..7
+        6543456
+        765.987
+        876....
+        987....
+        ";
+        assert_eq!(part1(input), "4");
+        assert_eq!(part2(input), "13");
+
+        let input = "
+        10..9..
+        2...8..
+        3...7..
+        4567654
+        ...8..3
+        ...9..2
+        .....01
+        ";
+        assert_eq!(part1(input), "3");
+
+        let input = "
+        .....0.
+        ..4321.
+        ..5..2.
+        ..6543.
+        ..7..4.
+        ..8765.
+        ..9....
+        ";
+        assert_eq!(part2(input), "3");
+
+        let input = "
+        012345
+        123456
+        234567
+        345678
+        4.6789
+        56789.
+        ";
+        assert_eq!(part2(input), "227");
+    }
+
+    #[test]
+    fn test_step_rules() {
+        let count = |input: &str, args: &[&str]| {
+            let (rule, _) = parse_rule(args).unwrap();
+            let mut map = parse(input);
+            map.rule = rule;
+            trailhead_stats(&map, usize::MAX)
+                .into_iter()
+                .map(|(score, rating)| (score, rating.trails))
+                .collect::<Vec<_>>()
+        };
+
+        let input = "
+        0.9
+        246
+        ...
+        ";
+        assert_eq!(count(input, &[]), vec![(0, 0)]);
+        assert_eq!(count(input, &["climb", "2"]), vec![(0, 0)]);
+        assert_eq!(count(input, &["climb", "3"]), vec![(1, 1)]);
+
+        let input = "
+        0.
+        .9
+        ";
+        assert_eq!(count(input, &["climb", "9"]), vec![(0, 0)]);
+        assert_eq!(count(input, &["climb", "9", "diagonal"]), vec![(1, 1)]);
+
+        let input = "
+        012345678
+        ........7
+        .......98
+        ";
+        assert_eq!(count(input, &[]), vec![(0, 0)]);
+        assert_eq!(count(input, &["descent", "1"]), vec![(1, 1)]);
+        assert_eq!(
+            command(input, &["count", "0", "descent", "1"]),
+            Some("score: 1\nrating: 0+\n".to_string())
+        );
+    }
 }
@@ -1,72 +1,256 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::ops::Add;
+
+use num_bigint::BigUint;
 
 fn parse(input: &str) -> Vec<usize> {
     input
         .trim()
         .lines()
-        .flat_map(|line| line.trim().split_whitespace().map(|s| s.parse().unwrap()))
+        .flat_map(|line| line.split_whitespace().map(|s| s.parse().unwrap()))
         .collect()
 }
 
-fn blink(number: usize) -> Vec<usize> {
-    match number {
-        0 => vec![1],
-        _ if number.to_string().len() % 2 == 0 => {
-            let str = number.to_string();
-            let (a, b) = str.split_at(str.len() / 2);
-            vec![a, b].iter().map(|s| s.parse().unwrap()).collect()
+// Numbers with a replacement are replaced. Otherwise, numbers whose digit
+// count in `base` is a multiple of `split` are split into `split` numbers of
+// equal digit count, and all other numbers are multiplied by `multiplier`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rules {
+    replacements: HashMap<usize, Vec<usize>>,
+    base: usize,
+    split: usize,
+    multiplier: usize,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            replacements: HashMap::from([(0, vec![1])]),
+            base: 10,
+            split: 2,
+            multiplier: 2024,
         }
-        _ => vec![number * 2024],
     }
 }
 
-fn count_length(
-    number: usize,
-    blink_count: usize,
-    max_blink_count: usize,
-    memo: &mut HashMap<(usize, usize), usize>,
-) -> usize {
-    if blink_count == max_blink_count {
-        return 1;
+impl Rules {
+    // Rule files hold one `<key> <value>` setting per line, with the same keys
+    // and values as the command line flags, e.g. `replace 0=1`, `base 10`,
+    // `split 2` or `multiply 2024`.
+    fn parse(input: &str) -> Option<Rules> {
+        let mut rules = Rules::default();
+        for line in input.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (key, value) = line.split_once(char::is_whitespace)?;
+            rules.set(key, value.trim())?;
+        }
+        Some(rules)
     }
 
-    if let Some(&length) = memo.get(&(number, blink_count)) {
-        return length;
+    fn set(&mut self, key: &str, value: &str) -> Option<()> {
+        match key {
+            "replace" => {
+                let (number, replacement) = value.split_once('=')?;
+                let replacement = replacement
+                    .split(',')
+                    .filter(|n| !n.is_empty())
+                    .map(|n| n.parse().ok())
+                    .collect::<Option<_>>()?;
+                self.replacements.insert(number.parse().ok()?, replacement);
+            }
+            "base" => self.base = value.parse().ok().filter(|base| *base >= 2)?,
+            "split" => self.split = value.parse().ok().filter(|split| *split >= 1)?,
+            "multiply" => self.multiplier = value.parse().ok()?,
+            _ => return None,
+        }
+        Some(())
     }
+}
 
-    let next_numbers = blink(number);
-    let length = next_numbers
-        .iter()
-        .map(|n| count_length(*n, blink_count + 1, max_blink_count, memo))
-        .sum();
-    memo.insert((number, blink_count), length);
-    length
+fn digit_count(number: usize, base: usize) -> u32 {
+    number.checked_ilog(base).unwrap_or(0) + 1
 }
 
-pub fn part1(input: &str) -> String {
-    let numbers = parse(input);
+// Returns `None` when a multiplied number does not fit in a `usize`.
+fn blink(number: usize, rules: &Rules) -> Option<Vec<usize>> {
+    if let Some(replacement) = rules.replacements.get(&number) {
+        return Some(replacement.clone());
+    }
+
+    let digits = digit_count(number, rules.base);
+    if digits.is_multiple_of(rules.split as u32) {
+        // Only a single part can be too wide for a power of the base.
+        let Some(divisor) = rules.base.checked_pow(digits / rules.split as u32) else {
+            return Some(vec![number]);
+        };
+        let mut parts = vec![0; rules.split];
+        let mut rest = number;
+        for part in parts.iter_mut().rev() {
+            *part = rest % divisor;
+            rest /= divisor;
+        }
+        Some(parts)
+    } else {
+        Some(vec![number.checked_mul(rules.multiplier)?])
+    }
+}
 
-    let mut memo = HashMap::new();
-    numbers
-        .iter()
-        .map(|n| count_length(*n, 0, 25, &mut memo))
-        .sum::<usize>()
-        .to_string()
+// Stone counts stay machine-sized while they fit, and switch to big integers
+// once they would overflow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Count {
+    Small(usize),
+    Big(BigUint),
 }
 
-pub fn part2(input: &str) -> String {
-    let numbers = parse(input);
+impl Count {
+    fn to_big(&self) -> BigUint {
+        match self {
+            Count::Small(n) => BigUint::from(*n),
+            Count::Big(n) => n.clone(),
+        }
+    }
+}
+
+impl Add<&Count> for Count {
+    type Output = Count;
+
+    fn add(self, other: &Count) -> Count {
+        match (&self, other) {
+            (Count::Small(a), Count::Small(b)) => match a.checked_add(*b) {
+                Some(sum) => Count::Small(sum),
+                None => Count::Big(self.to_big() + other.to_big()),
+            },
+            _ => Count::Big(self.to_big() + other.to_big()),
+        }
+    }
+}
+
+impl fmt::Display for Count {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Count::Small(n) => write!(f, "{n}"),
+            Count::Big(n) => write!(f, "{n}"),
+        }
+    }
+}
+
+// Stones with the same number evolve the same way, so only the number of
+// stones per number is tracked from one blink to the next.
+struct Stones {
+    histogram: HashMap<usize, Count>,
+}
+
+impl Stones {
+    fn new(numbers: &[usize]) -> Self {
+        let mut histogram: HashMap<usize, Count> = HashMap::new();
+        for number in numbers {
+            let count = histogram.remove(number).unwrap_or(Count::Small(0));
+            histogram.insert(*number, count + &Count::Small(1));
+        }
+        Stones { histogram }
+    }
+
+    fn blink(&self, rules: &Rules) -> Option<Self> {
+        let mut histogram: HashMap<usize, Count> = HashMap::new();
+        for (number, count) in &self.histogram {
+            for next_number in blink(*number, rules)? {
+                let next_count = histogram.remove(&next_number).unwrap_or(Count::Small(0));
+                histogram.insert(next_number, next_count + count);
+            }
+        }
+        Some(Stones { histogram })
+    }
+
+    fn count(&self) -> Count {
+        self.histogram
+            .values()
+            .fold(Count::Small(0), |total, count| total + count)
+    }
+
+    fn distinct(&self) -> usize {
+        self.histogram.len()
+    }
+
+    fn largest(&self) -> Option<usize> {
+        self.histogram.keys().max().copied()
+    }
+}
+
+// On overflow, returns the blink that overflowed along with the rows before it.
+fn blink_stats(
+    numbers: &[usize],
+    blinks: usize,
+    rules: &Rules,
+) -> Result<Vec<String>, (usize, Vec<String>)> {
+    let mut stones = Stones::new(numbers);
+    let mut stats = vec![];
+    for blink in 0..=blinks {
+        if blink > 0 {
+            stones = match stones.blink(rules) {
+                Some(stones) => stones,
+                None => return Err((blink, stats)),
+            };
+        }
+        stats.push(format!(
+            "{blink},{},{},{}",
+            stones.count(),
+            stones.distinct(),
+            stones.largest().map_or(String::new(), |n| n.to_string())
+        ));
+    }
+    Ok(stats)
+}
+
+fn count_stones(numbers: &[usize], blinks: usize, rules: &Rules) -> String {
+    let mut stones = Stones::new(numbers);
+    for blink in 1..=blinks {
+        stones = match stones.blink(rules) {
+            Some(stones) => stones,
+            None => return format!("stone numbers overflow at blink {blink}"),
+        };
+    }
+    stones.count().to_string()
+}
+
+// Flags are applied in order, so rules loaded with `--rules <file>` can be
+// adjusted by the flags that follow.
+pub fn command(input: &str, args: &[&str]) -> Option<String> {
+    let mut rules = Rules::default();
+    let mut blinks = None;
+    for flag in args.chunks(2) {
+        match flag {
+            ["--blinks", n] => blinks = Some(n.parse().ok()?),
+            ["--rules", path] => rules = Rules::parse(&fs::read_to_string(path).ok()?)?,
+            [key, value] => rules.set(key.strip_prefix("--")?, value)?,
+            _ => return None,
+        }
+    }
+
+    let (stats, overflow) = match blink_stats(&parse(input), blinks?, &rules) {
+        Ok(stats) => (stats, None),
+        Err((blink, stats)) => (stats, Some(blink)),
+    };
+    let mut output = format!("blink,stones,distinct,largest\n{}\n", stats.join("\n"));
+    if let Some(blink) = overflow {
+        output += &format!("stone numbers overflow at blink {blink}\n");
+    }
+    Some(output)
+}
+
+pub fn part1(input: &str) -> String {
+    count_stones(&parse(input), 25, &Rules::default())
+}
 
-    let mut memo = HashMap::new();
-    numbers
-        .iter()
-        .map(|n| count_length(*n, 0, 75, &mut memo))
-        .sum::<usize>()
-        .to_string()
+pub fn part2(input: &str) -> String {
+    count_stones(&parse(input), 75, &Rules::default())
 }
 
 #[cfg(test)]
 mod tests {
+    use rand::Rng;
+
     use super::*;
 
     #[test]
@@ -80,4 +264,124 @@ mod tests {
         let input = "125 17";
         assert_eq!(part2(input), "65601038650482");
     }
+
+    #[test]
+    fn test_stones() {
+        let mut stones = Stones::new(&parse("125 17"));
+        assert_eq!(stones.count(), Count::Small(2));
+
+        let expected = [
+            (3, 3, 253000),
+            (4, 4, 14168),
+            (5, 5, 28676032),
+            (9, 8, 6032),
+            (13, 12, 1036288),
+            (22, 15, 2097446912),
+        ];
+        for (count, distinct, largest) in expected {
+            stones = stones.blink(&Rules::default()).unwrap();
+            assert_eq!(stones.count(), Count::Small(count));
+            assert_eq!(stones.distinct(), distinct);
+            assert_eq!(stones.largest(), Some(largest));
+        }
+    }
+
+    #[test]
+    fn test_big_count() {
+        let mut stones = Stones::new(&parse("125 17"));
+        for _ in 0..105 {
+            stones = stones.blink(&Rules::default()).unwrap();
+        }
+        assert!(matches!(stones.count(), Count::Small(_)));
+
+        let count = stones.count().to_big();
+        let stones = stones.blink(&Rules::default()).unwrap();
+        assert!(matches!(stones.count(), Count::Big(_)));
+        assert!(stones.count().to_big() > count);
+    }
+
+    #[test]
+    fn test_rules() {
+        let rules = Rules::parse(
+            "
+            replace 0=1
+            replace 7=
+            base 2
+            split 3
+            multiply 3
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(blink(0, &rules), Some(vec![1]));
+        assert_eq!(blink(7, &rules), Some(vec![]));
+        assert_eq!(blink(0b101110, &rules), Some(vec![0b10, 0b11, 0b10]));
+        assert_eq!(blink(0b1011, &rules), Some(vec![0b100001]));
+        assert_eq!(Rules::parse("split 0"), None);
+        assert_eq!(Rules::parse("divide 2"), None);
+
+        let mut numbers = vec![5];
+        let mut stones = Stones::new(&numbers);
+        for _ in 0..4 {
+            numbers = numbers
+                .iter()
+                .flat_map(|n| blink(*n, &rules).unwrap())
+                .collect();
+            stones = stones.blink(&rules).unwrap();
+        }
+        assert_eq!(stones.count(), Count::Small(numbers.len()));
+    }
+
+    #[test]
+    fn test_split_random() {
+        let mut rng = rand::rng();
+
+        for _ in 0..10000 {
+            let bits = rng.random_range(1..54);
+            let number: usize = rng.random_range(0..1 << bits);
+            let str = number.to_string();
+            let expected: Vec<usize> = match number {
+                0 => vec![1],
+                _ if str.len().is_multiple_of(2) => {
+                    let (a, b) = str.split_at(str.len() / 2);
+                    vec![a.parse().unwrap(), b.parse().unwrap()]
+                }
+                _ => vec![number * 2024],
+            };
+            assert_eq!(blink(number, &Rules::default()), Some(expected));
+        }
+        assert_eq!(
+            blink(usize::MAX, &Rules::default()),
+            Some(vec![1844674407, 3709551615])
+        );
+    }
+
+    #[test]
+    fn test_default_rules() {
+        assert_eq!(blink(0, &Rules::default()), Some(vec![1]));
+        assert_eq!(blink(1000, &Rules::default()), Some(vec![10, 0]));
+        assert_eq!(blink(99, &Rules::default()), Some(vec![9, 9]));
+        assert_eq!(blink(999, &Rules::default()), Some(vec![2021976]));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let rules = Rules {
+            split: 3,
+            ..Rules::default()
+        };
+        assert_eq!(blink(usize::MAX / 1000, &rules), None);
+        assert_eq!(
+            count_stones(&[usize::MAX / 1000], 1, &rules),
+            "stone numbers overflow at blink 1"
+        );
+
+        let output = command("125 17", &["--split", "3", "--blinks", "60"]).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[1], "0,2,2,125");
+        assert!(lines
+            .last()
+            .unwrap()
+            .starts_with("stone numbers overflow at blink "));
+    }
 }
@@ -1,11 +1,58 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use itertools::Itertools;
 
 type Pos = (isize, isize);
-type Region = HashSet<Pos>;
 
 const DIRS: [Pos; 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
 
-fn parse(input: &str) -> Vec<Region> {
+const ALL_DIRS: [Pos; 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
+// Which neighbouring plots with the same label belong to the same region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Connectivity {
+    Orthogonal,
+    Diagonal,
+}
+
+impl Connectivity {
+    fn dirs(self) -> &'static [Pos] {
+        match self {
+            Connectivity::Orthogonal => &DIRS,
+            Connectivity::Diagonal => &ALL_DIRS,
+        }
+    }
+}
+
+struct Region {
+    label: char,
+    plots: HashSet<Pos>,
+}
+
+// A side is a straight run of fence segments facing `dir`, listed by the plots
+// of the region behind them.
+struct Side {
+    dir: Pos,
+    plots: Vec<Pos>,
+}
+
+struct BoundingBox {
+    top: isize,
+    left: isize,
+    bottom: isize,
+    right: isize,
+}
+
+fn parse(input: &str, connectivity: Connectivity) -> Vec<Region> {
     let grid: HashMap<Pos, char> = input
         .trim()
         .lines()
@@ -19,94 +66,624 @@ fn parse(input: &str) -> Vec<Region> {
 
     let mut regions: Vec<Region> = Vec::new();
     let mut visited: HashSet<Pos> = HashSet::new();
-    for (pos, char) in &grid {
-        if visited.contains(&pos) {
+    for (pos, char) in grid.iter().sorted() {
+        if visited.contains(pos) {
             continue;
         }
-        let mut region = HashSet::new();
+        let mut plots = HashSet::new();
         let mut queue = vec![*pos];
         while let Some(pos) = queue.pop() {
-            if region.contains(&pos) {
+            if plots.contains(&pos) {
                 continue;
             }
-            region.insert(pos);
-            for dir in DIRS {
+            plots.insert(pos);
+            for dir in connectivity.dirs() {
                 let neighbor_pos = (pos.0 + dir.0, pos.1 + dir.1);
                 if grid.get(&neighbor_pos).is_some_and(|c| c == char) {
                     queue.push(neighbor_pos);
                 }
             }
         }
-        visited.extend(region.iter());
-        regions.push(region);
+        visited.extend(plots.iter());
+        regions.push(Region {
+            label: *char,
+            plots,
+        });
     }
     regions
 }
 
-fn get_area(region: &Region) -> usize {
-    region.len()
+impl Region {
+    fn area(&self) -> usize {
+        self.plots.len()
+    }
+
+    fn perimeter(&self) -> usize {
+        self.plots
+            .iter()
+            .map(|pos| {
+                DIRS.iter()
+                    .map(|dir| (pos.0 + dir.0, pos.1 + dir.1))
+                    .filter(|neighbor_pos| self.plots.contains(neighbor_pos))
+                    .count()
+            })
+            .map(|region_facing_sides| DIRS.len() - region_facing_sides)
+            .sum()
+    }
+
+    fn sides(&self) -> Vec<Side> {
+        let mut edges: HashMap<(isize, Pos), Vec<isize>> = HashMap::new();
+        for pos in &self.plots {
+            for dir in DIRS {
+                let neighbor_pos = (pos.0 + dir.0, pos.1 + dir.1);
+                if !self.plots.contains(&neighbor_pos) {
+                    let (key, value) = if dir.0 == 0 {
+                        (pos.1, pos.0)
+                    } else {
+                        (pos.0, pos.1)
+                    };
+                    edges.entry((key, dir)).or_default().push(value);
+                }
+            }
+        }
+
+        edges
+            .into_iter()
+            .sorted()
+            .flat_map(|((key, dir), mut indices)| {
+                indices.sort();
+                let mut runs: Vec<Vec<isize>> = vec![];
+                for index in indices {
+                    match runs.last_mut() {
+                        Some(run) if run.last() == Some(&(index - 1)) => run.push(index),
+                        _ => runs.push(vec![index]),
+                    }
+                }
+                runs.into_iter().map(move |run| Side {
+                    dir,
+                    plots: run
+                        .into_iter()
+                        .map(|index| {
+                            if dir.0 == 0 {
+                                (index, key)
+                            } else {
+                                (key, index)
+                            }
+                        })
+                        .collect(),
+                })
+            })
+            .collect()
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox {
+            top: self.plots.iter().map(|pos| pos.0).min().unwrap(),
+            left: self.plots.iter().map(|pos| pos.1).min().unwrap(),
+            bottom: self.plots.iter().map(|pos| pos.0).max().unwrap(),
+            right: self.plots.iter().map(|pos| pos.1).max().unwrap(),
+        }
+    }
+
+    // Labels the cells around and between the plots that are not part of the
+    // region. Label 0 is the outside, every other label is a hole.
+    fn surroundings(&self) -> HashMap<Pos, usize> {
+        let bbox = self.bounding_box();
+        let rows = bbox.top - 1..=bbox.bottom + 1;
+        let cols = bbox.left - 1..=bbox.right + 1;
+
+        let mut labels: HashMap<Pos, usize> = HashMap::new();
+        let cells = rows.clone().cartesian_product(cols.clone());
+        for start in cells.filter(|pos| !self.plots.contains(pos)) {
+            if labels.contains_key(&start) {
+                continue;
+            }
+            let label = labels.values().max().map_or(0, |label| label + 1);
+            let mut queue = VecDeque::from([start]);
+            while let Some(pos) = queue.pop_front() {
+                if labels.contains_key(&pos) {
+                    continue;
+                }
+                labels.insert(pos, label);
+                queue.extend(
+                    DIRS.iter()
+                        .map(|dir| (pos.0 + dir.0, pos.1 + dir.1))
+                        .filter(|pos| rows.contains(&pos.0) && cols.contains(&pos.1))
+                        .filter(|pos| !self.plots.contains(pos)),
+                );
+            }
+        }
+        labels
+    }
+
+    fn holes(&self) -> usize {
+        self.surroundings().values().unique().count() - 1
+    }
+
+    // The sides facing the outside, and the sides facing holes.
+    fn outer_and_inner_sides(&self) -> (usize, usize) {
+        let surroundings = self.surroundings();
+        let (outer, inner): (Vec<Side>, Vec<Side>) = self.sides().into_iter().partition(|side| {
+            let (pos, dir) = (side.plots[0], side.dir);
+            surroundings[&(pos.0 + dir.0, pos.1 + dir.1)] == 0
+        });
+        (outer.len(), inner.len())
+    }
+
+    // A region is convex when every row and every column crosses it in one
+    // unbroken run of plots.
+    fn is_convex(&self) -> bool {
+        let contiguous = |lines: HashMap<isize, Vec<isize>>| {
+            lines.into_values().all(|mut indices| {
+                indices.sort();
+                indices.windows(2).all(|pair| pair[1] == pair[0] + 1)
+            })
+        };
+        contiguous(self.plots.iter().map(|&(i, j)| (i, j)).into_group_map())
+            && contiguous(self.plots.iter().map(|&(i, j)| (j, i)).into_group_map())
+    }
+}
+
+const REPORT_COLUMNS: [&str; 12] = [
+    "label",
+    "area",
+    "perimeter",
+    "sides",
+    "outer_sides",
+    "inner_sides",
+    "holes",
+    "top",
+    "left",
+    "bottom",
+    "right",
+    "convex",
+];
+
+fn report_row(region: &Region) -> [String; 12] {
+    let bbox = region.bounding_box();
+    let (outer_sides, inner_sides) = region.outer_and_inner_sides();
+    [
+        region.label.to_string(),
+        region.area().to_string(),
+        region.perimeter().to_string(),
+        (outer_sides + inner_sides).to_string(),
+        outer_sides.to_string(),
+        inner_sides.to_string(),
+        region.holes().to_string(),
+        bbox.top.to_string(),
+        bbox.left.to_string(),
+        bbox.bottom.to_string(),
+        bbox.right.to_string(),
+        region.is_convex().to_string(),
+    ]
 }
 
-fn get_perimeter(region: &Region) -> usize {
-    region
+fn json_string(value: &str) -> String {
+    let escaped: String = value
+        .chars()
+        .map(|char| match char {
+            '"' => "\\\"".to_string(),
+            '\\' => "\\\\".to_string(),
+            _ if char.is_control() => format!("\\u{:04x}", char as u32),
+            _ => char.to_string(),
+        })
+        .collect();
+    format!("\"{escaped}\"")
+}
+
+fn report(input: &str, json: bool, connectivity: Connectivity) -> String {
+    let rows: Vec<[String; 12]> = parse(input, connectivity).iter().map(report_row).collect();
+
+    if json {
+        let objects = rows.iter().map(|row| {
+            let fields = REPORT_COLUMNS
+                .iter()
+                .zip(row)
+                .map(|(key, value)| match *key {
+                    "label" => format!("\"{key}\":{}", json_string(value)),
+                    _ => format!("\"{key}\":{value}"),
+                });
+            format!("{{{}}}", fields.format(","))
+        });
+        return format!("[{}]\n", objects.format(","));
+    }
+
+    let widths: Vec<usize> = (0..REPORT_COLUMNS.len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].len())
+                .fold(REPORT_COLUMNS[column].len(), usize::max)
+        })
+        .collect();
+    let format_row = |row: &[String]| {
+        row.iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{value:>width$}"))
+            .join(" ")
+            + "\n"
+    };
+
+    format_row(&REPORT_COLUMNS.map(String::from)) + &rows.iter().map(|row| format_row(row)).join("")
+}
+
+// Box-drawing characters indexed by the edges meeting at a grid point: up = 1,
+// right = 2, down = 4, left = 8.
+const LIGHT_JUNCTIONS: [char; 16] = [
+    ' ', '╵', '╶', '└', '╷', '│', '┌', '├', '╴', '┘', '─', '┴', '┐', '┤', '┬', '┼',
+];
+const HEAVY_JUNCTIONS: [char; 16] = [
+    ' ', '╹', '╺', '┗', '╻', '┃', '┏', '┣', '╸', '┛', '━', '┻', '┓', '┫', '┳', '╋',
+];
+
+const COLORS: [u8; 12] = [31, 32, 33, 34, 35, 36, 91, 92, 93, 94, 95, 96];
+
+fn paint(char: char, color: Option<u8>, bold: bool) -> String {
+    match (color, bold) {
+        (Some(color), true) => format!("\x1b[1;{color}m{char}\x1b[0m"),
+        (Some(color), false) => format!("\x1b[{color}m{char}\x1b[0m"),
+        (None, _) => char.to_string(),
+    }
+}
+
+// Gives every region the first colour none of its neighbours has, so that
+// regions next to each other stand apart.
+fn region_colors(regions: &[Region], region_of: &HashMap<Pos, usize>) -> Vec<u8> {
+    let mut colors: Vec<u8> = vec![];
+    for region in regions {
+        let taken: HashSet<u8> = region
+            .plots
+            .iter()
+            .flat_map(|pos| {
+                ALL_DIRS
+                    .iter()
+                    .map(move |dir| (pos.0 + dir.0, pos.1 + dir.1))
+            })
+            .filter_map(|pos| region_of.get(&pos))
+            .filter_map(|&other| colors.get(other).copied())
+            .collect();
+        let color = COLORS
+            .iter()
+            .find(|color| !taken.contains(color))
+            .unwrap_or(&COLORS[colors.len() % COLORS.len()]);
+        colors.push(*color);
+    }
+    colors
+}
+
+// Draws the garden at twice its size, with plot labels on odd rows and columns
+// and the fences between regions on the even ones. The sides of the region
+// holding the `selected` plot are drawn heavy, each in its own colour when
+// colours are on, so that it shows which fence segments were merged.
+fn render(input: &str, connectivity: Connectivity, selected: Option<Pos>, color: bool) -> String {
+    let regions = parse(input, connectivity);
+    let region_of: HashMap<Pos, usize> = regions
+        .iter()
+        .enumerate()
+        .flat_map(|(index, region)| region.plots.iter().map(move |pos| (*pos, index)))
+        .collect();
+    let colors = region_colors(&regions, &region_of);
+    let rows = region_of.keys().map(|pos| pos.0 + 1).max().unwrap_or(0);
+    let cols = region_of.keys().map(|pos| pos.1 + 1).max().unwrap_or(0);
+
+    let selected = selected
+        .and_then(|pos| region_of.get(&pos))
+        .map(|&index| &regions[index]);
+    let sides = selected.map(Region::sides).unwrap_or_default();
+    // Fence segments are keyed by the two cells they separate, in order.
+    let segment = |a: Pos, b: Pos| (a.min(b), a.max(b));
+    let highlighted: HashMap<(Pos, Pos), usize> = sides
         .iter()
-        .map(|pos| {
-            DIRS.iter()
-                .map(|dir| (pos.0 + dir.0, pos.1 + dir.1))
-                .filter(|neighbor_pos| region.contains(neighbor_pos))
-                .count()
+        .enumerate()
+        .flat_map(|(index, side)| {
+            side.plots.iter().map(move |pos| {
+                let neighbor_pos = (pos.0 + side.dir.0, pos.1 + side.dir.1);
+                (segment(*pos, neighbor_pos), index)
+            })
         })
-        .map(|region_facing_sides| DIRS.len() - region_facing_sides)
-        .sum()
+        .collect();
+    let is_fence = |a: Pos, b: Pos| region_of.get(&a) != region_of.get(&b);
+    let side_color = |index: usize| color.then_some(COLORS[index % COLORS.len()]);
+    let draw_segment =
+        |a: Pos, b: Pos, light: char, heavy: char| match highlighted.get(&segment(a, b)) {
+            Some(&index) => paint(heavy, side_color(index), true),
+            None if is_fence(a, b) => light.to_string(),
+            None => " ".to_string(),
+        };
+
+    let mut lines: Vec<String> = (0..=2 * rows)
+        .map(|y| {
+            (0..=2 * cols)
+                .map(|x| {
+                    let (i, j) = (y / 2, x / 2);
+                    match (y % 2, x % 2) {
+                        (1, 1) => {
+                            let index = region_of[&(i, j)];
+                            paint(regions[index].label, color.then_some(colors[index]), false)
+                        }
+                        (0, 1) => draw_segment((i - 1, j), (i, j), '─', '━'),
+                        (1, 0) => draw_segment((i, j - 1), (i, j), '│', '┃'),
+                        _ => {
+                            // The segments above, right of, below and left of
+                            // the grid point.
+                            let segments = [
+                                ((i - 1, j - 1), (i - 1, j)),
+                                ((i - 1, j), (i, j)),
+                                ((i, j - 1), (i, j)),
+                                ((i - 1, j - 1), (i, j - 1)),
+                            ];
+                            let mask = |present: &dyn Fn(Pos, Pos) -> bool| {
+                                segments
+                                    .iter()
+                                    .enumerate()
+                                    .filter(|(_, (a, b))| present(*a, *b))
+                                    .map(|(bit, _)| 1 << bit)
+                                    .sum::<usize>()
+                            };
+                            let side = segments
+                                .iter()
+                                .find_map(|(a, b)| highlighted.get(&segment(*a, *b)));
+                            match side {
+                                Some(&index) => {
+                                    let heavy =
+                                        mask(&|a, b| highlighted.contains_key(&segment(a, b)));
+                                    paint(HEAVY_JUNCTIONS[heavy], side_color(index), true)
+                                }
+                                None => LIGHT_JUNCTIONS[mask(&is_fence)].to_string(),
+                            }
+                        }
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect();
+
+    if let Some(region) = selected {
+        lines.push(format!(
+            "region {}: area {}, perimeter {}, sides {}",
+            region.label,
+            region.area(),
+            region.perimeter(),
+            sides.len()
+        ));
+    }
+    lines.join("\n") + "\n"
+}
+
+pub fn command(input: &str, args: &[&str]) -> Option<String> {
+    let (args, connectivity) = match args {
+        [rest @ .., "diagonal"] => (rest, Connectivity::Diagonal),
+        _ => (args, Connectivity::Orthogonal),
+    };
+
+    let pricing = match args {
+        ["render", ref options @ ..] => {
+            let (selected, options) = match options {
+                ["plot", i, j, rest @ ..] => (Some((i.parse().ok()?, j.parse().ok()?)), rest),
+                _ => (None, options),
+            };
+            let color = match options {
+                [] => false,
+                ["color"] => true,
+                _ => return None,
+            };
+            return Some(render(input, connectivity, selected, color));
+        }
+        ["report"] => return Some(report(input, false, connectivity)),
+        ["report", "json"] => return Some(report(input, true, connectivity)),
+        ["price", "perimeter"] => Pricing::Perimeter,
+        ["price", "sides"] => Pricing::Sides,
+        ["price", "bulk", side, segment] => Pricing::Bulk {
+            side: side.parse().ok()?,
+            segment: segment.parse().ok()?,
+        },
+        ["price", "corners", convex, concave] => Pricing::Corners {
+            convex: convex.parse().ok()?,
+            concave: concave.parse().ok()?,
+        },
+        _ => return None,
+    };
+    Some(format!("{}\n", total_price(input, pricing, connectivity)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct RegionStats {
+    label: char,
+    area: usize,
+    perimeter: usize,
+    convex_corners: usize,
+    concave_corners: usize,
+}
+
+impl RegionStats {
+    // A region has as many sides as corners.
+    fn sides(&self) -> usize {
+        self.convex_corners + self.concave_corners
+    }
+}
+
+// Union-find over component ids, where every root carries the statistics of
+// its whole component.
+struct Components {
+    parents: Vec<usize>,
+    stats: Vec<RegionStats>,
+}
+
+impl Components {
+    fn add(&mut self, label: char) -> usize {
+        self.parents.push(self.parents.len());
+        self.stats.push(RegionStats {
+            label,
+            area: 0,
+            perimeter: 0,
+            convex_corners: 0,
+            concave_corners: 0,
+        });
+        self.parents.len() - 1
+    }
+
+    fn find(&mut self, id: usize) -> usize {
+        let mut root = id;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+        let mut id = id;
+        while self.parents[id] != root {
+            (id, self.parents[id]) = (self.parents[id], root);
+        }
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) -> usize {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return a;
+        }
+        let (root, child) = match self.stats[a].area >= self.stats[b].area {
+            true => (a, b),
+            false => (b, a),
+        };
+        self.parents[child] = root;
+        let child_stats = self.stats[child];
+        let stats = &mut self.stats[root];
+        stats.area += child_stats.area;
+        stats.perimeter += child_stats.perimeter;
+        stats.convex_corners += child_stats.convex_corners;
+        stats.concave_corners += child_stats.concave_corners;
+        root
+    }
+
+    fn stats_mut(&mut self, id: usize) -> &mut RegionStats {
+        let root = self.find(id);
+        &mut self.stats[root]
+    }
 }
 
-fn get_sides(region: &Region) -> usize {
-    let mut edges: HashMap<(isize, Pos), HashSet<isize>> = HashMap::new();
-    for pos in region {
-        for dir in DIRS {
-            let neighbor_pos = (pos.0 + dir.0, pos.1 + dir.1);
-            if !region.contains(&neighbor_pos) {
-                let (key, value) = if dir.0 == 0 {
-                    (pos.1, pos.0)
-                } else {
-                    (pos.0, pos.1)
+// Labels regions one row at a time, keeping only the previous row around.
+// Whether a grid point between two rows is a corner of the plots around it
+// depends only on those four plots, so corners are counted once both rows next
+// to a grid point are known.
+fn region_stats<'a>(
+    rows: impl Iterator<Item = &'a str>,
+    connectivity: Connectivity,
+) -> Vec<RegionStats> {
+    let mut components = Components {
+        parents: vec![],
+        stats: vec![],
+    };
+    let mut prev_row: Vec<(char, usize)> = vec![];
+
+    let mut rows = rows.map(str::trim).filter(|row| !row.is_empty());
+    loop {
+        let row = rows.next();
+        let mut cur_row: Vec<(char, usize)> = vec![];
+        for (j, label) in row.unwrap_or_default().chars().enumerate() {
+            let same =
+                |plot: Option<&(char, usize)>| plot.copied().filter(|&(other, _)| other == label);
+            let left = same(cur_row.last());
+            let up = same(prev_row.get(j));
+            let diagonals = match connectivity {
+                Connectivity::Orthogonal => [None, None],
+                Connectivity::Diagonal => [
+                    same(j.checked_sub(1).and_then(|j| prev_row.get(j))),
+                    same(prev_row.get(j + 1)),
+                ],
+            };
+            // A plot only starts a new component when it joins none of the
+            // plots before it.
+            let mut joined = [left, up].into_iter().chain(diagonals).flatten();
+            let id = match joined.next() {
+                Some((_, id)) => joined.fold(id, |id, (_, other)| components.union(id, other)),
+                None => components.add(label),
+            };
+            let perimeter = 4 - 2 * (left.is_some() as usize + up.is_some() as usize);
+            let stats = components.stats_mut(id);
+            stats.area += 1;
+            stats.perimeter += perimeter;
+            cur_row.push((label, id));
+        }
+
+        let width = prev_row.len().max(cur_row.len());
+        for j in 0..=width {
+            let window = [
+                j.checked_sub(1).and_then(|j| prev_row.get(j)),
+                prev_row.get(j),
+                j.checked_sub(1).and_then(|j| cur_row.get(j)),
+                cur_row.get(j),
+            ];
+            // The horizontal, vertical and diagonal neighbours of each plot.
+            let neighbors = [(1, 2, 3), (0, 3, 2), (3, 0, 1), (2, 1, 0)];
+            for (plot, (h, v, d)) in neighbors.iter().enumerate() {
+                let Some(&(label, id)) = window[plot] else {
+                    continue;
                 };
-                edges.entry((key, dir)).or_default().insert(value);
+                let same = |k: usize| window[k].is_some_and(|(other, _)| *other == label);
+                if !same(*h) && !same(*v) {
+                    components.stats_mut(id).convex_corners += 1;
+                } else if same(*h) && same(*v) && !same(*d) {
+                    components.stats_mut(id).concave_corners += 1;
+                }
             }
         }
+
+        match row {
+            Some(_) => prev_row = cur_row,
+            None => break,
+        }
     }
 
-    edges
-        .values()
-        .map(|indices| {
-            indices
-                .iter()
-                .filter(|&index| !indices.contains(&(index + 1)))
-                .count()
-        })
+    (0..components.parents.len())
+        .filter(|&id| components.parents[id] == id)
+        .map(|id| components.stats[id])
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Pricing {
+    // Every fence segment costs the same.
+    Perimeter,
+    // Every straight side costs the same, however long.
+    Sides,
+    // Each side costs `side`, plus `segment` for every segment after its first.
+    Bulk { side: usize, segment: usize },
+    // Each corner costs `convex` or `concave` depending on which way it turns.
+    Corners { convex: usize, concave: usize },
+}
+
+impl Pricing {
+    fn price(self, stats: &RegionStats) -> usize {
+        let fence = match self {
+            Pricing::Perimeter => stats.perimeter,
+            Pricing::Sides => stats.sides(),
+            Pricing::Bulk { side, segment } => {
+                side * stats.sides() + segment * (stats.perimeter - stats.sides())
+            }
+            Pricing::Corners { convex, concave } => {
+                convex * stats.convex_corners + concave * stats.concave_corners
+            }
+        };
+        stats.area * fence
+    }
+}
+
+fn total_price(input: &str, pricing: Pricing, connectivity: Connectivity) -> usize {
+    region_stats(input.lines(), connectivity)
+        .iter()
+        .map(|stats| pricing.price(stats))
         .sum()
 }
 
 pub fn part1(input: &str) -> String {
-    let regions = parse(input);
-    regions
-        .iter()
-        .map(|region| get_area(region) * get_perimeter(region))
-        .sum::<usize>()
-        .to_string()
+    total_price(input, Pricing::Perimeter, Connectivity::Orthogonal).to_string()
 }
 
 pub fn part2(input: &str) -> String {
-    let regions = parse(input);
-    regions
-        .iter()
-        .map(|region| get_area(region) * get_sides(region))
-        .sum::<usize>()
-        .to_string()
+    total_price(input, Pricing::Sides, Connectivity::Orthogonal).to_string()
 }
 
 #[cfg(test)]
 mod tests {
+    use rand::Rng;
+
     use super::*;
 
     #[test]
@@ -195,4 +772,223 @@ mod tests {
         ";
         assert_eq!(part2(input5), "1206");
     }
+
+    #[test]
+    fn test_region_analytics() {
+        let input = "
+        OOOOO
+        OXOXO
+        OOOOO
+        OXOXO
+        OOOOO
+        ";
+        let regions = parse(input, Connectivity::Orthogonal);
+        let outer = &regions[0];
+
+        assert_eq!(regions.len(), 5);
+        assert_eq!(outer.label, 'O');
+        assert_eq!(outer.holes(), 4);
+        assert_eq!(outer.outer_and_inner_sides(), (4, 16));
+        assert!(!outer.is_convex());
+        assert!(regions[1].is_convex());
+        assert_eq!(regions[1].holes(), 0);
+
+        let input = "
+        AAAAAA
+        AAABBA
+        AAABBA
+        ABBAAA
+        ABBAAA
+        AAAAAA
+        ";
+        let regions = parse(input, Connectivity::Orthogonal);
+        let outer = &regions[0];
+
+        assert_eq!(outer.holes(), 2);
+        assert_eq!(outer.outer_and_inner_sides(), (4, 8));
+        let bbox = regions[1].bounding_box();
+        assert_eq!((bbox.top, bbox.left, bbox.bottom, bbox.right), (1, 3, 2, 4));
+
+        let input = "
+        EEE
+        EXX
+        EEE
+        ";
+        let regions = parse(input, Connectivity::Orthogonal);
+
+        assert_eq!(regions[0].holes(), 0);
+        assert_eq!(regions[0].outer_and_inner_sides(), (8, 0));
+        assert!(!regions[0].is_convex());
+    }
+
+    #[test]
+    fn test_report() {
+        let input = "
+        AB
+        AA
+        ";
+
+        assert_eq!(
+            report(input, true, Connectivity::Orthogonal),
+            "[{\"label\":\"A\",\"area\":3,\"perimeter\":8,\"sides\":6,\"outer_sides\":6,\"inner_sides\":0,\"holes\":0,\"top\":0,\"left\":0,\"bottom\":1,\"right\":1,\"convex\":true},\
+            {\"label\":\"B\",\"area\":1,\"perimeter\":4,\"sides\":4,\"outer_sides\":4,\"inner_sides\":0,\"holes\":0,\"top\":0,\"left\":1,\"bottom\":0,\"right\":1,\"convex\":true}]\n"
+        );
+
+        let labels: Vec<String> = report(r#""\"#, true, Connectivity::Orthogonal)
+            .split(',')
+            .filter(|field| field.contains("label"))
+            .map(str::to_string)
+            .collect();
+        assert_eq!(labels, [r#"[{"label":"\"""#, r#"{"label":"\\""#]);
+    }
+
+    #[test]
+    fn test_render() {
+        let input = "
+        AB
+        AA
+        ";
+
+        assert_eq!(
+            render(input, Connectivity::Orthogonal, None, false),
+            "┌─┬─┐\n│A│B│\n│ └─┤\n│A A│\n└───┘\n"
+        );
+        assert_eq!(
+            render(input, Connectivity::Orthogonal, Some((0, 1)), false),
+            "┌─┏━┓\n│A┃B┃\n│ ┗━┛\n│A A│\n└───┘\nregion B: area 1, perimeter 4, sides 4\n"
+        );
+
+        let colored = render(input, Connectivity::Orthogonal, Some((0, 1)), true);
+        assert!(colored.contains("\x1b[31mA\x1b[0m"));
+        assert!(colored.contains("\x1b[32mB\x1b[0m"));
+        assert_eq!(colored.matches("\x1b[1;").count(), 8);
+    }
+
+    fn flood_fill_stats(
+        input: &str,
+        connectivity: Connectivity,
+    ) -> Vec<(char, usize, usize, usize)> {
+        parse(input, connectivity)
+            .iter()
+            .map(|region| {
+                (
+                    region.label,
+                    region.area(),
+                    region.perimeter(),
+                    region.sides().len(),
+                )
+            })
+            .sorted()
+            .collect()
+    }
+
+    fn streaming_stats(
+        input: &str,
+        connectivity: Connectivity,
+    ) -> Vec<(char, usize, usize, usize)> {
+        region_stats(input.lines(), connectivity)
+            .iter()
+            .map(|stats| (stats.label, stats.area, stats.perimeter, stats.sides()))
+            .sorted()
+            .collect()
+    }
+
+    #[test]
+    fn test_region_stats() {
+        let inputs = [
+            "AAAA\nBBCD\nBBCC\nEEEC",
+            "OOOOO\nOXOXO\nOOOOO\nOXOXO\nOOOOO",
+            "EEEEE\nEXXXX\nEEEEE\nEXXXX\nEEEEE",
+            "AAAAAA\nAAABBA\nAAABBA\nABBAAA\nABBAAA\nAAAAAA",
+            include_str!("../../data/d12.txt"),
+        ];
+        for input in inputs {
+            for connectivity in [Connectivity::Orthogonal, Connectivity::Diagonal] {
+                assert_eq!(
+                    streaming_stats(input, connectivity),
+                    flood_fill_stats(input, connectivity)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_region_stats_random() {
+        let mut rng = rand::rng();
+
+        for _ in 0..200 {
+            let (rows, cols) = (rng.random_range(1..12), rng.random_range(1..12));
+            let input = (0..rows)
+                .map(|_| {
+                    (0..cols)
+                        .map(|_| ['A', 'B', 'C'][rng.random_range(0..3)])
+                        .collect::<String>()
+                })
+                .join("\n");
+            for connectivity in [Connectivity::Orthogonal, Connectivity::Diagonal] {
+                assert_eq!(
+                    streaming_stats(&input, connectivity),
+                    flood_fill_stats(&input, connectivity),
+                    "garden\n{input}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_pricing() {
+        let input = include_str!("../../data/d12.txt");
+        let price = |pricing| total_price(input, pricing, Connectivity::Orthogonal);
+
+        let bulk = Pricing::Bulk {
+            side: 1,
+            segment: 1,
+        };
+        assert_eq!(price(bulk), price(Pricing::Perimeter));
+        let bulk = Pricing::Bulk {
+            side: 1,
+            segment: 0,
+        };
+        assert_eq!(price(bulk), price(Pricing::Sides));
+        let corners = Pricing::Corners {
+            convex: 1,
+            concave: 1,
+        };
+        assert_eq!(price(corners), price(Pricing::Sides));
+
+        // Outward corners outnumber inward ones by four around every region,
+        // and inward ones outnumber outward ones by four around every hole.
+        let input = "
+        OOOOO
+        OXOXO
+        OOOOO
+        OXOXO
+        OOOOO
+        ";
+        let corners = Pricing::Corners {
+            convex: 1,
+            concave: 0,
+        };
+        assert_eq!(
+            total_price(input, corners, Connectivity::Orthogonal),
+            21 * 4 + 4 * 4
+        );
+    }
+
+    #[test]
+    fn test_diagonal() {
+        let input = "
+        AB
+        BA
+        ";
+        assert_eq!(
+            command(input, &["price", "sides"]),
+            Some("16\n".to_string())
+        );
+        assert_eq!(
+            command(input, &["price", "sides", "diagonal"]),
+            Some("32\n".to_string())
+        );
+        assert_eq!(command(input, &["price", "bulk", "1"]), None);
+    }
 }
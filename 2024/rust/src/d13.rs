@@ -1,3 +1,5 @@
+use std::fmt;
+
 use itertools::Itertools;
 
 #[derive(Debug)]
@@ -33,74 +35,219 @@ fn parse(input: &str) -> Vec<Game> {
         .collect()
 }
 
-fn is_close(a: f64, b: f64) -> bool {
-    (a - b).abs() < 1e-4
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    a_cost: usize,
+    b_cost: usize,
+    // The most times either button may be pressed.
+    press_limit: Option<usize>,
+    // Added to both coordinates of every prize.
+    prize_offset: usize,
 }
 
-fn inverse(matrix: (f64, f64, f64, f64)) -> Option<(f64, f64, f64, f64)> {
-    let (a, b, c, d) = matrix;
-    let det = a * d - b * c;
-    if is_close(det, 0.0) {
-        None
-    } else {
-        Some((d / det, -b / det, -c / det, a / det))
+const PART1: Settings = Settings {
+    a_cost: 3,
+    b_cost: 1,
+    press_limit: Some(100),
+    prize_offset: 0,
+};
+
+const PART2: Settings = Settings {
+    a_cost: 3,
+    b_cost: 1,
+    press_limit: None,
+    prize_offset: 10000000000000,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unsolvable {
+    OffLine,
+    Fractional,
+    Negative,
+    OverLimit(usize),
+}
+
+impl fmt::Display for Unsolvable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unsolvable::OffLine => write!(f, "the prize is off the line both buttons move along"),
+            Unsolvable::Fractional => write!(f, "no whole number of presses reaches the prize"),
+            Unsolvable::Negative => write!(f, "reaching the prize needs negative presses"),
+            Unsolvable::OverLimit(limit) => {
+                write!(f, "reaching the prize needs more than {limit} presses")
+            }
+        }
+    }
+}
+
+// Returns `(g, x, y)` with `g = gcd(a, b) = a * x + b * y`.
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        return (a, 1, 0);
     }
+    let (g, x, y) = extended_gcd(b, a % b);
+    (g, y, x - a / b * y)
 }
 
-fn product(matrix: (f64, f64, f64, f64), vector: (f64, f64)) -> (f64, f64) {
-    (
-        matrix.0 * vector.0 + matrix.1 * vector.1,
-        matrix.2 * vector.0 + matrix.3 * vector.1,
-    )
+fn within_limit((a, b): (i128, i128), settings: &Settings) -> Result<(i128, i128), Unsolvable> {
+    match settings.press_limit {
+        Some(limit) if a > limit as i128 || b > limit as i128 => Err(Unsolvable::OverLimit(limit)),
+        _ => Ok((a, b)),
+    }
+}
+
+// Finds the cheapest non-negative `(a, b)` with `a * u + b * v = w`.
+fn cheapest_presses(
+    u: i128,
+    v: i128,
+    w: i128,
+    settings: &Settings,
+) -> Result<(i128, i128), Unsolvable> {
+    match (u, v) {
+        (0, 0) if w == 0 => return Ok((0, 0)),
+        (0, 0) => return Err(Unsolvable::Fractional),
+        (0, _) if w % v == 0 => return within_limit((0, w / v), settings),
+        (_, 0) if w % u == 0 => return within_limit((w / u, 0), settings),
+        (0, _) | (_, 0) => return Err(Unsolvable::Fractional),
+        _ => {}
+    }
+    let (g, x, y) = extended_gcd(u, v);
+    if w % g != 0 {
+        return Err(Unsolvable::Fractional);
+    }
+    let (a, b) = (x * (w / g), y * (w / g));
+    // Every solution is `(a + k * step_a, b - k * step_b)`, so the presses stay
+    // within bounds for a range of `k`, and the cost is linear in `k`.
+    let (step_a, step_b) = (v / g, u / g);
+    let mut min_k = -a.div_euclid(step_a);
+    let mut max_k = b.div_euclid(step_b);
+    if min_k > max_k {
+        return Err(Unsolvable::Negative);
+    }
+    if let Some(limit) = settings.press_limit {
+        min_k = min_k.max(-(limit as i128 - b).div_euclid(step_b));
+        max_k = max_k.min((limit as i128 - a).div_euclid(step_a));
+        if min_k > max_k {
+            return Err(Unsolvable::OverLimit(limit));
+        }
+    }
+    let slope = settings.a_cost as i128 * step_a - settings.b_cost as i128 * step_b;
+    let k = match slope > 0 {
+        true => min_k,
+        false => max_k,
+    };
+    Ok((a + k * step_a, b - k * step_b))
 }
 
-fn calc_button_clicks(game: &Game) -> Option<(usize, usize)> {
+// Solves `a * (ax, ay) + b * (bx, by) = (px, py)` exactly with Cramer's rule.
+// When the buttons move the claw along the same line, the prize has to lie on
+// it too, and the cheapest presses are found along that line alone.
+fn calc_button_clicks(game: &Game, settings: &Settings) -> Result<(usize, usize), Unsolvable> {
     let (ax, ay, bx, by, px, py) = (
-        game.ax as f64,
-        game.ay as f64,
-        game.bx as f64,
-        game.by as f64,
-        game.px as f64,
-        game.py as f64,
+        game.ax as i128,
+        game.ay as i128,
+        game.bx as i128,
+        game.by as i128,
+        game.px as i128 + settings.prize_offset as i128,
+        game.py as i128 + settings.prize_offset as i128,
     );
-    if let Some(inverse_matrix) = inverse((ax, bx, ay, by)) {
-        let (a, b) = product(inverse_matrix, (px, py));
-        if is_close(a, a.round()) && is_close(b, b.round()) && a >= 0.0 && b >= 0.0 {
-            return Some((a.round() as usize, b.round() as usize));
+    let det = ax * by - bx * ay;
+    let (a, b) = if det == 0 {
+        // Buttons that never move along an axis cannot reach a prize off it.
+        let off_axis = (ax == 0 && bx == 0 && px != 0) || (ay == 0 && by == 0 && py != 0);
+        if ax * py - ay * px != 0 || bx * py - by * px != 0 || off_axis {
+            return Err(Unsolvable::OffLine);
+        }
+        match ax != 0 || bx != 0 {
+            true => cheapest_presses(ax, bx, px, settings)?,
+            false => cheapest_presses(ay, by, py, settings)?,
         }
+    } else {
+        let (a, b) = (px * by - bx * py, ax * py - px * ay);
+        if a % det != 0 || b % det != 0 {
+            return Err(Unsolvable::Fractional);
+        }
+        let (a, b) = (a / det, b / det);
+        if a < 0 || b < 0 {
+            return Err(Unsolvable::Negative);
+        }
+        within_limit((a, b), settings)?
+    };
+    match (usize::try_from(a), usize::try_from(b)) {
+        (Ok(a), Ok(b)) => Ok((a, b)),
+        _ => Err(Unsolvable::OverLimit(usize::MAX)),
     }
-    None
 }
 
-pub fn part1(input: &str) -> String {
-    let games = parse(input);
+fn cost((a, b): (usize, usize), settings: &Settings) -> usize {
+    a * settings.a_cost + b * settings.b_cost
+}
+
+fn machines(input: &str, settings: &Settings) -> String {
+    let results: Vec<_> = parse(input)
+        .iter()
+        .map(|game| calc_button_clicks(game, settings))
+        .collect();
+    let total: usize = results
+        .iter()
+        .flatten()
+        .map(|presses| cost(*presses, settings))
+        .sum();
+
+    results
+        .iter()
+        .enumerate()
+        .map(|(i, result)| match result {
+            Ok((a, b)) => format!(
+                "machine {}: {a} A + {b} B for {} tokens\n",
+                i + 1,
+                cost((*a, *b), settings)
+            ),
+            Err(reason) => format!("machine {}: {reason}\n", i + 1),
+        })
+        .join("")
+        + &format!("total: {total} tokens\n")
+}
 
-    games
+fn total_cost(input: &str, settings: &Settings) -> usize {
+    parse(input)
         .iter()
-        .filter_map(calc_button_clicks)
-        .map(|(a, b)| a * 3 + b)
-        .sum::<usize>()
-        .to_string()
+        .filter_map(|game| calc_button_clicks(game, settings).ok())
+        .map(|presses| cost(presses, settings))
+        .sum()
+}
+
+pub fn command(input: &str, args: &[&str]) -> Option<String> {
+    let (mut settings, flags) = match args {
+        ["machines", "part", "2", flags @ ..] => (PART2, flags),
+        ["machines", "part", "1", flags @ ..] | ["machines", flags @ ..] => (PART1, flags),
+        _ => return None,
+    };
+    for flag in flags.chunks(2) {
+        match flag {
+            ["--a-cost", n] => settings.a_cost = n.parse().ok()?,
+            ["--b-cost", n] => settings.b_cost = n.parse().ok()?,
+            ["--limit", "none"] => settings.press_limit = None,
+            ["--limit", n] => settings.press_limit = Some(n.parse().ok()?),
+            ["--offset", n] => settings.prize_offset = n.parse().ok()?,
+            _ => return None,
+        }
+    }
+    Some(machines(input, &settings))
+}
+
+pub fn part1(input: &str) -> String {
+    total_cost(input, &PART1).to_string()
 }
 
 pub fn part2(input: &str) -> String {
-    let games = parse(input);
-
-    games
-        .into_iter()
-        .map(|mut game| {
-            game.px += 10000000000000;
-            game.py += 10000000000000;
-            game
-        })
-        .filter_map(|game| calc_button_clicks(&game))
-        .map(|(a, b)| a * 3 + b)
-        .sum::<usize>()
-        .to_string()
+    total_cost(input, &PART2).to_string()
 }
 
 #[cfg(test)]
 mod tests {
+    use rand::Rng;
+
     use super::*;
 
     #[test]
@@ -146,4 +293,195 @@ mod tests {
         ";
         assert_eq!(part2(input), "875318608908");
     }
+
+    fn brute_force(game: &Game) -> Vec<(usize, usize)> {
+        (0..=game.px / game.ax)
+            .flat_map(|a| (0..=game.px / game.bx).map(move |b| (a, b)))
+            .filter(|(a, b)| {
+                a * game.ax + b * game.bx == game.px && a * game.ay + b * game.by == game.py
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_calc_button_clicks_random() {
+        let mut rng = rand::rng();
+
+        for _ in 0..2000 {
+            let mut game = Game {
+                ax: rng.random_range(1..20),
+                ay: rng.random_range(1..20),
+                bx: rng.random_range(1..20),
+                by: rng.random_range(1..20),
+                px: 0,
+                py: 0,
+            };
+            if rng.random_bool(0.3) {
+                // Buttons moving along the same line.
+                let (x, y) = (rng.random_range(1..5), rng.random_range(1..5));
+                let (m, n) = (rng.random_range(1..5), rng.random_range(1..5));
+                (game.ax, game.ay, game.bx, game.by) = (m * x, m * y, n * x, n * y);
+            }
+            let (a, b) = (rng.random_range(0..40), rng.random_range(0..40));
+            game.px = a * game.ax + b * game.bx + rng.random_range(0..3);
+            game.py = a * game.ay + b * game.by + rng.random_range(0..3);
+
+            let solutions = brute_force(&game);
+            if game.ax * game.by != game.bx * game.ay {
+                assert!(solutions.len() <= 1, "{game:?}");
+            }
+            let settings = Settings {
+                a_cost: rng.random_range(1..5),
+                b_cost: rng.random_range(1..5),
+                press_limit: rng.random_bool(0.5).then(|| rng.random_range(5..50)),
+                prize_offset: 0,
+            };
+            let cheapest =
+                calc_button_clicks(&game, &settings).map(|presses| cost(presses, &settings));
+            let expected = solutions
+                .into_iter()
+                .filter(|&(a, b)| {
+                    settings
+                        .press_limit
+                        .is_none_or(|limit| a <= limit && b <= limit)
+                })
+                .map(|presses| cost(presses, &settings))
+                .min();
+            assert_eq!(cheapest.ok(), expected, "{game:?} {settings:?}");
+        }
+    }
+
+    #[test]
+    fn test_negative_presses() {
+        // Reaching the prize would take -1 presses of button B.
+        let game = Game {
+            ax: 2,
+            ay: 1,
+            bx: 1,
+            by: 1,
+            px: 3,
+            py: 1,
+        };
+        let settings = Settings {
+            press_limit: None,
+            ..PART1
+        };
+        assert_eq!(
+            calc_button_clicks(&game, &settings),
+            Err(Unsolvable::Negative)
+        );
+    }
+
+    #[test]
+    fn test_collinear() {
+        let settings = Settings {
+            press_limit: None,
+            ..PART1
+        };
+        let clicks = |game| calc_button_clicks(&game, &settings);
+        let game = |ax, ay, bx, by, px, py| Game {
+            ax,
+            ay,
+            bx,
+            by,
+            px,
+            py,
+        };
+
+        // Button B goes further per token.
+        assert_eq!(clicks(game(2, 4, 1, 2, 10, 20)), Ok((0, 10)));
+        // Button A goes further per token, but cannot reach the prize alone.
+        assert_eq!(clicks(game(8, 4, 2, 1, 10, 5)), Ok((1, 1)));
+        assert_eq!(clicks(game(8, 4, 2, 1, 16, 8)), Ok((2, 0)));
+        // The prize is off the line.
+        assert_eq!(clicks(game(1, 1, 2, 2, 3, 4)), Err(Unsolvable::OffLine));
+        // On the line, but out of reach of whole presses.
+        assert_eq!(clicks(game(2, 2, 4, 4, 3, 3)), Err(Unsolvable::Fractional));
+        assert_eq!(clicks(game(4, 4, 6, 6, 2, 2)), Err(Unsolvable::Negative));
+        // Only moving vertically.
+        assert_eq!(clicks(game(0, 3, 0, 2, 0, 7)), Ok((1, 2)));
+        // Buttons that do not move the claw at all.
+        assert_eq!(clicks(game(0, 0, 0, 0, 5, 0)), Err(Unsolvable::OffLine));
+        assert_eq!(clicks(game(0, 0, 0, 0, 0, 5)), Err(Unsolvable::OffLine));
+        assert_eq!(clicks(game(0, 0, 0, 0, 0, 0)), Ok((0, 0)));
+    }
+
+    #[test]
+    fn test_large_offset() {
+        let settings = Settings {
+            press_limit: None,
+            prize_offset: usize::MAX,
+            ..PART1
+        };
+        let game = |px, py| Game {
+            ax: 2,
+            ay: 0,
+            bx: 0,
+            by: 2,
+            px,
+            py,
+        };
+
+        assert_eq!(
+            calc_button_clicks(&game(1, 1), &settings),
+            Ok((1 << 63, 1 << 63))
+        );
+        assert_eq!(
+            calc_button_clicks(&game(0, 0), &settings),
+            Err(Unsolvable::Fractional)
+        );
+        assert_eq!(
+            calc_button_clicks(&game(usize::MAX, 1), &settings),
+            Ok((usize::MAX, 1 << 63))
+        );
+        // More presses than fit in a `usize`.
+        let game = Game {
+            ax: 1,
+            by: 1,
+            ..game(1, 1)
+        };
+        assert_eq!(
+            calc_button_clicks(&game, &settings),
+            Err(Unsolvable::OverLimit(usize::MAX))
+        );
+    }
+
+    #[test]
+    fn test_machines() {
+        let input = "
+        Button A: X+94, Y+34
+        Button B: X+22, Y+67
+        Prize: X=8400, Y=5400
+
+        Button A: X+26, Y+66
+        Button B: X+67, Y+21
+        Prize: X=12748, Y=12176
+
+        Button A: X+17, Y+86
+        Button B: X+84, Y+37
+        Prize: X=7870, Y=6450
+        ";
+
+        assert_eq!(
+            command(input, &["machines"]),
+            Some(
+                "machine 1: 80 A + 40 B for 280 tokens\n\
+                machine 2: no whole number of presses reaches the prize\n\
+                machine 3: 38 A + 86 B for 200 tokens\n\
+                total: 480 tokens\n"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            command(input, &["machines", "--limit", "80", "--a-cost", "1"]),
+            Some(
+                "machine 1: 80 A + 40 B for 120 tokens\n\
+                machine 2: no whole number of presses reaches the prize\n\
+                machine 3: reaching the prize needs more than 80 presses\n\
+                total: 120 tokens\n"
+                    .to_string()
+            )
+        );
+        assert_eq!(command(input, &["machines", "--limit"]), None);
+    }
 }
@@ -1,5 +1,7 @@
 use std::{
-    collections::HashSet,
+    fs,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
     sync::atomic::{AtomicIsize, Ordering},
 };
 
@@ -31,15 +33,17 @@ fn parse(input: &str) -> Vec<Robot> {
         .collect()
 }
 
-fn wait(robot: &Robot, time: isize) -> (isize, isize) {
-    let (x_max, y_max) = (X.load(Ordering::Relaxed), Y.load(Ordering::Relaxed));
+fn size() -> (isize, isize) {
+    (X.load(Ordering::Relaxed), Y.load(Ordering::Relaxed))
+}
+
+fn wait(robot: &Robot, time: isize, (x_max, y_max): (isize, isize)) -> (isize, isize) {
     let x = robot.x + robot.vx * time;
     let y = robot.y + robot.vy * time;
     (((x % x_max) + x_max) % x_max, ((y % y_max) + y_max) % y_max)
 }
 
-fn count(positions: &Vec<(isize, isize)>) -> [usize; 4] {
-    let (x, y) = (X.load(Ordering::Relaxed), Y.load(Ordering::Relaxed));
+fn count(positions: &[(isize, isize)], (x, y): (isize, isize)) -> [usize; 4] {
     let (rx1, rx2) = (0..x / 2, x / 2 + 1..x);
     let (ry1, ry2) = (0..y / 2, y / 2 + 1..y);
     let ranges = [(&rx1, &ry1), (&rx1, &ry2), (&rx2, &ry1), (&rx2, &ry2)];
@@ -59,48 +63,257 @@ pub fn part1(input: &str) -> String {
     let robots = parse(input);
     let time = 100;
 
-    let positions = robots.iter().map(|robot| wait(robot, time)).collect();
-    count(&positions).iter().product::<usize>().to_string()
+    let positions: Vec<_> = robots
+        .iter()
+        .map(|robot| wait(robot, time, size()))
+        .collect();
+    count(&positions, size())
+        .iter()
+        .product::<usize>()
+        .to_string()
+}
+
+// The number of robots on each tile at `time`, row by row.
+fn tiles(robots: &[Robot], time: isize, (x_max, y_max): (isize, isize)) -> Vec<Vec<usize>> {
+    let mut tiles = vec![vec![0; x_max as usize]; y_max as usize];
+    for robot in robots {
+        let (x, y) = wait(robot, time, (x_max, y_max));
+        tiles[y as usize][x as usize] += 1;
+    }
+    tiles
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    // Plain bitmap, black wherever there is a robot.
+    Pbm,
+    // Plain greymap, brighter the more robots share a tile.
+    Pgm,
+    // The tiles as drawn in the puzzle, with robot counts or `.`.
+    Text,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Pbm => "pbm",
+            Format::Pgm => "pgm",
+            Format::Text => "txt",
+        }
+    }
+}
+
+fn render(tiles: &[Vec<usize>], format: Format) -> String {
+    let (width, height) = (tiles[0].len(), tiles.len());
+    let max = tiles.iter().flatten().max().copied().unwrap_or(0).max(1);
+    let header = match format {
+        Format::Pbm => format!("P1\n{width} {height}\n"),
+        Format::Pgm => format!("P2\n{width} {height}\n{max}\n"),
+        Format::Text => String::new(),
+    };
+    let rows = tiles.iter().map(|row| match format {
+        Format::Pbm => row.iter().map(|&n| (n > 0) as usize).join(" "),
+        Format::Pgm => row.iter().join(" "),
+        Format::Text => row
+            .iter()
+            .map(|&n| match n {
+                0 => '.',
+                1..=9 => char::from_digit(n as u32, 10).unwrap(),
+                _ => '+',
+            })
+            .collect(),
+    });
+    header + &rows.map(|row| row + "\n").join("")
+}
+
+// Writes one file per time step into `dir`, named after the time step.
+fn export(
+    robots: &[Robot],
+    times: impl Iterator<Item = isize>,
+    size: (isize, isize),
+    format: Format,
+    dir: &Path,
+) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    times
+        .map(|time| {
+            let path = dir.join(format!("{time:05}.{}", format.extension()));
+            fs::write(&path, render(&tiles(robots, time, size), format))?;
+            Ok(path)
+        })
+        .collect()
+}
+
+const PLAY_HELP: &str = "[enter] next, p previous, +n/-n step, g n go to, q quit";
+
+// Shows one time step after another, reading a control per line of `input`.
+fn play(
+    robots: &[Robot],
+    size: (isize, isize),
+    mut time: isize,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> io::Result<()> {
+    let mut lines = input.lines();
+    loop {
+        write!(
+            output,
+            "\x1b[2J\x1b[Htime: {time}\n{}{PLAY_HELP}\n",
+            render(&tiles(robots, time, size), Format::Text)
+        )?;
+        output.flush()?;
+
+        let Some(line) = lines.next().transpose()? else {
+            return Ok(());
+        };
+        let line = line.trim();
+        time = match line.split_whitespace().collect_vec().as_slice() {
+            [] | ["n"] => time + 1,
+            ["p"] => time - 1,
+            ["q"] => return Ok(()),
+            ["g", to] => to.parse().unwrap_or(time),
+            _ => match line.parse::<isize>() {
+                Ok(step) if line.starts_with(['+', '-']) => time + step,
+                _ => time,
+            },
+        }
+        .max(0);
+    }
 }
 
-fn _print(positions: &HashSet<(isize, isize)>) {
-    for y in 0..Y.load(Ordering::Relaxed) {
-        for x in 0..X.load(Ordering::Relaxed) {
-            if positions.contains(&(x, y)) {
-                print!("#");
-            } else {
-                print!(".");
-            }
+// Shannon entropy in bits of where the robots are, low when they bunch up on
+// few tiles.
+fn entropy(positions: &[(isize, isize)]) -> f64 {
+    let n = positions.len() as f64;
+    positions
+        .iter()
+        .counts()
+        .values()
+        .map(|&count| count as f64 / n * (n / count as f64).log2())
+        .sum()
+}
+
+fn time_series(robots: &[Robot], until: isize, size: (isize, isize)) -> String {
+    let header = "time,top_left,bottom_left,top_right,bottom_right,safety_factor,\
+        min_x,min_y,max_x,max_y,entropy\n";
+    let rows = (0..=until).map(|time| {
+        let positions: Vec<_> = robots.iter().map(|robot| wait(robot, time, size)).collect();
+        let quadrants = count(&positions, size);
+        let (xs, ys): (Vec<isize>, Vec<isize>) = positions.iter().copied().unzip();
+        let bounds = [
+            xs.iter().min(),
+            ys.iter().min(),
+            xs.iter().max(),
+            ys.iter().max(),
+        ]
+        .iter()
+        .map(|bound| bound.map_or(String::new(), |bound| bound.to_string()))
+        .join(",");
+        format!(
+            "{time},{},{},{bounds},{:.4}\n",
+            quadrants.iter().join(","),
+            quadrants.iter().product::<usize>(),
+            entropy(&positions)
+        )
+    });
+    header.to_string() + &rows.collect::<String>()
+}
+
+pub fn command(input: &str, args: &[&str]) -> Option<String> {
+    let robots = parse(input);
+
+    match args {
+        ["export", format, dir, from, rest @ ..] => {
+            let format = match *format {
+                "pbm" => Format::Pbm,
+                "pgm" => Format::Pgm,
+                "txt" => Format::Text,
+                _ => return None,
+            };
+            let from = from.parse().ok()?;
+            let to = match rest {
+                [] => from,
+                [to] => to.parse().ok()?,
+                _ => return None,
+            };
+            let paths = export(&robots, from..=to, size(), format, Path::new(dir)).ok()?;
+            Some(
+                paths
+                    .iter()
+                    .map(|path| format!("{}\n", path.display()))
+                    .join(""),
+            )
+        }
+        ["stats", until] => Some(time_series(&robots, until.parse().ok()?, size())),
+        ["play", rest @ ..] => {
+            let time = match rest {
+                [] => 0,
+                [time] => time.parse().ok()?,
+                _ => return None,
+            };
+            play(&robots, size(), time, io::stdin().lock(), io::stdout()).ok()?;
+            Some(String::new())
         }
-        println!();
+        _ => None,
+    }
+}
+
+// How spread out the robots are along one axis at `time`, as the variance of
+// their coordinates times the square of the number of robots.
+fn spread(starts_and_velocities: &[(isize, isize)], len: isize, time: isize) -> i128 {
+    let (n, sum, sum_of_squares) = starts_and_velocities.iter().fold(
+        (0, 0, 0),
+        |(n, sum, sum_of_squares), (start, velocity)| {
+            let pos = (start + velocity * time).rem_euclid(len) as i128;
+            (n + 1, sum + pos, sum_of_squares + pos * pos)
+        },
+    );
+    n * sum_of_squares - sum * sum
+}
+
+// Returns `(g, x, y)` with `g = gcd(a, b) = a * x + b * y`.
+fn extended_gcd(a: isize, b: isize) -> (isize, isize, isize) {
+    if b == 0 {
+        return (a, 1, 0);
     }
-    std::thread::sleep(std::time::Duration::from_millis(750));
+    let (g, x, y) = extended_gcd(b, a % b);
+    (g, y, x - a / b * y)
 }
 
-pub fn part2(_input: &str) -> String {
-    // let robots = parse(_input);
+// Returns the smallest non-negative `t` with `t % m == a` and `t % n == b`.
+fn chinese_remainder((a, m): (isize, isize), (b, n): (isize, isize)) -> Option<isize> {
+    let (g, inverse, _) = extended_gcd(m, n);
+    if (b - a) % g != 0 {
+        return None;
+    }
+    let k = ((b - a) / g * inverse).rem_euclid(n / g);
+    Some((a + k * m).rem_euclid(m / g * n))
+}
 
-    // let (x_max, y_max) = (X.load(Ordering::Relaxed), Y.load(Ordering::Relaxed));
-    // let repeating_time = x_max * y_max;
-    // for time in 0..repeating_time {
-    //     let positions: HashSet<(isize, isize)> =
-    //         robots.iter().map(|robot| wait(robot, time)).collect();
+// The robots are least spread out when they arrange themselves into the
+// picture. Their x coordinates repeat every `x_max` seconds and their y
+// coordinates every `y_max` seconds, so each axis is searched on its own and the
+// two times are combined.
+fn easter_egg_time(robots: &[Robot], (x_max, y_max): (isize, isize)) -> Option<isize> {
+    let xs: Vec<_> = robots.iter().map(|robot| (robot.x, robot.vx)).collect();
+    let ys: Vec<_> = robots.iter().map(|robot| (robot.y, robot.vy)).collect();
+    let x_time = (0..x_max).min_by_key(|&time| spread(&xs, x_max, time))?;
+    let y_time = (0..y_max).min_by_key(|&time| spread(&ys, y_max, time))?;
+    chinese_remainder((x_time, x_max), (y_time, y_max))
+}
 
-    //     positions
-    //         .iter()
-    //         .any(|(x, y)| (0..5).all(|i| positions.contains(&(x + i, y + i))))
-    //         .then(|| {
-    //             println!("\n");
-    //             println!("time: {:?}", time);
-    //             _print(&positions);
-    //         });
-    // }
+pub fn part2(input: &str) -> String {
+    let robots = parse(input);
 
-    "6876".to_string()
+    easter_egg_time(&robots, size()).unwrap().to_string()
 }
 
 #[cfg(test)]
 mod tests {
+    use std::env;
+
+    use rand::Rng;
+
     use super::*;
 
     #[test]
@@ -123,4 +336,129 @@ mod tests {
         ";
         assert_eq!(part1(input), "12");
     }
+
+    #[test]
+    fn test_chinese_remainder() {
+        assert_eq!(chinese_remainder((2, 3), (3, 5)), Some(8));
+        assert_eq!(chinese_remainder((0, 101), (0, 103)), Some(0));
+        assert_eq!(chinese_remainder((3, 4), (1, 6)), Some(7));
+        assert_eq!(chinese_remainder((0, 4), (1, 6)), None);
+    }
+
+    #[test]
+    fn test_easter_egg_time() {
+        let mut rng = rand::rng();
+        let size: (isize, isize) = (31, 37);
+        let time = rng.random_range(0..31 * 37) as isize;
+
+        // A square picture at `time`, among robots wandering around at random.
+        let picture = (10..18).cartesian_product(10..18);
+        let noise = (0..30).map(|_| {
+            (
+                rng.random_range(0..31) as isize,
+                rng.random_range(0..37) as isize,
+            )
+        });
+        let robots: Vec<Robot> = picture
+            .chain(noise.collect::<Vec<_>>())
+            .map(|(x, y)| {
+                let vx = rng.random_range(-10..=10) as isize;
+                let vy = rng.random_range(-10..=10) as isize;
+                Robot {
+                    x: (x - vx * time).rem_euclid(size.0),
+                    y: (y - vy * time).rem_euclid(size.1),
+                    vx,
+                    vy,
+                }
+            })
+            .collect();
+        assert_eq!(easter_egg_time(&robots, size), Some(time));
+
+        let robots = parse(include_str!("../../data/d14.txt"));
+        assert_eq!(easter_egg_time(&robots, (101, 103)), Some(6876));
+    }
+
+    #[test]
+    fn test_render() {
+        let robots = parse("p=2,4 v=2,-3\np=0,0 v=0,0\np=4,0 v=0,0");
+        let tiles = tiles(&robots, 1, (11, 7));
+
+        assert_eq!(
+            render(&tiles, Format::Text),
+            "1...1......\n....1......\n...........\n...........\n...........\n...........\n...........\n"
+        );
+        assert!(
+            render(&tiles, Format::Pbm).starts_with("P1\n11 7\n1 0 0 0 1 0 0 0 0 0 0\n0 0 0 0 1 ")
+        );
+        assert!(render(&tiles, Format::Pgm).starts_with("P2\n11 7\n1\n1 0 0 0 1 "));
+    }
+
+    #[test]
+    fn test_export() {
+        let robots = parse("p=2,4 v=2,-3");
+        let dir = env::temp_dir().join(format!("d14-frames-{}", std::process::id()));
+        let paths = export(&robots, 3..=5, (11, 7), Format::Pgm, &dir).unwrap();
+
+        assert_eq!(paths.len(), 3);
+        assert_eq!(paths[0], dir.join("00003.pgm"));
+        assert!(fs::read_to_string(&paths[2])
+            .unwrap()
+            .starts_with("P2\n11 7\n1\n"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_play() {
+        let robots = parse("p=2,4 v=2,-3");
+        let mut output = vec![];
+        play(
+            &robots,
+            (11, 7),
+            0,
+            "\n+5\np\ng 100\n-200\nq\nn\n".as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let times: Vec<&str> = output
+            .lines()
+            .filter_map(|line| line.split("time: ").nth(1))
+            .collect();
+        assert_eq!(times, ["0", "1", "6", "5", "100", "0"]);
+    }
+
+    #[test]
+    fn test_time_series() {
+        let input = "
+        p=0,4 v=3,-3
+        p=6,3 v=-1,-3
+        p=10,3 v=-1,2
+        p=2,0 v=2,-1
+        p=0,0 v=1,3
+        p=3,0 v=-2,-2
+        p=7,6 v=-1,-3
+        p=3,0 v=-1,-2
+        p=9,3 v=2,3
+        p=7,3 v=-1,2
+        p=2,4 v=2,-3
+        p=9,5 v=-3,-3
+        ";
+        let series = time_series(&parse(input), 100, (11, 7));
+        let rows: Vec<&str> = series.lines().collect();
+
+        assert_eq!(rows.len(), 102);
+        assert!(rows[101].starts_with("100,1,4,3,1,12,"));
+
+        // Two robots meeting on the same tile.
+        let series = time_series(&parse("p=0,0 v=1,1\np=2,2 v=0,0"), 2, (11, 7));
+        assert_eq!(
+            series.lines().skip(1).collect_vec(),
+            [
+                "0,2,0,0,0,0,0,0,2,2,1.0000",
+                "1,2,0,0,0,0,1,1,2,2,1.0000",
+                "2,2,0,0,0,0,2,2,2,2,0.0000"
+            ]
+        );
+    }
 }
@@ -1,267 +1,456 @@
-use std::collections::HashSet;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fs;
 
-type Coord = (isize, isize);
+use itertools::Itertools;
 
-mod part1 {
-    use super::*;
+type Coord = (isize, isize);
 
-    pub struct Map {
-        robot: Coord,
-        boxes: HashSet<Coord>,
-        walls: HashSet<Coord>,
-    }
+// The robot and every box with its width, in order.
+type State = (Coord, Vec<(Coord, isize)>);
+
+#[derive(Clone)]
+struct Map {
+    robot: Coord,
+    walls: HashSet<Coord>,
+    // Every box by its leftmost tile, with its width.
+    boxes: HashMap<Coord, isize>,
+    // The leftmost tile of the box covering each tile that has one.
+    box_tiles: HashMap<Coord, Coord>,
+}
 
-    impl Map {
-        pub fn boxes_gps(&self) -> HashSet<usize> {
-            self.boxes
-                .iter()
-                .map(|(i, j)| (i * 100 + j) as usize)
-                .collect()
+impl Map {
+    fn add_box(&mut self, (i, j): Coord, width: isize) {
+        self.boxes.insert((i, j), width);
+        for k in 0..width {
+            self.box_tiles.insert((i, j + k), (i, j));
         }
+    }
 
-        pub fn move_robot(&mut self, dir: Coord) {
-            if let Some(coords) = self._move_check(self.robot, dir) {
-                self.robot = (self.robot.0 + dir.0, self.robot.1 + dir.1);
-                self.boxes.retain(|coord| !coords.contains(coord));
-                coords.iter().for_each(|coord| {
-                    self.boxes.insert((coord.0 + dir.0, coord.1 + dir.1));
-                });
-            }
+    fn remove_box(&mut self, (i, j): Coord) -> isize {
+        let width = self.boxes.remove(&(i, j)).unwrap();
+        for k in 0..width {
+            self.box_tiles.remove(&(i, j + k));
         }
+        width
+    }
 
-        fn _move_check(&self, pos: Coord, dir: Coord) -> Option<HashSet<Coord>> {
-            let next_coord = (pos.0 + dir.0, pos.1 + dir.1);
+    fn boxes_gps(&self) -> usize {
+        self.boxes.keys().map(|(i, j)| (i * 100 + j) as usize).sum()
+    }
 
-            if self.walls.contains(&next_coord) {
+    // The boxes the robot would push moving in `dir`, or `None` if one of them
+    // would hit a wall. A box pushes whatever is on the tiles it moves into
+    // that it does not already cover itself, however wide it is.
+    fn pushed_boxes(&self, dir: Coord) -> Option<HashSet<Coord>> {
+        let mut boxes = HashSet::new();
+        let mut tiles = vec![(self.robot.0 + dir.0, self.robot.1 + dir.1)];
+        while let Some(tile) = tiles.pop() {
+            if self.walls.contains(&tile) {
                 return None;
             }
+            if let Some(&(i, j)) = self.box_tiles.get(&tile) {
+                if boxes.insert((i, j)) {
+                    let width = self.boxes[&(i, j)];
+                    tiles.extend((0..width).map(|k| (i + dir.0, j + k + dir.1)));
+                }
+            }
+        }
+        Some(boxes)
+    }
 
-            if self.boxes.contains(&next_coord) {
-                return self._move_check(next_coord, dir).map(|mut set| {
-                    set.insert(next_coord);
-                    set
-                });
+    fn shift_boxes(&mut self, boxes: &[Coord], dir: Coord) {
+        let widths: Vec<(Coord, isize)> = boxes
+            .iter()
+            .map(|&pos| (pos, self.remove_box(pos)))
+            .collect();
+        for ((i, j), width) in widths {
+            self.add_box((i + dir.0, j + dir.1), width);
+        }
+    }
+
+    fn move_robot(&mut self, dir: Coord) -> Step {
+        match self.pushed_boxes(dir) {
+            Some(boxes) => {
+                let pushed: Vec<Coord> = boxes.into_iter().sorted().collect();
+                self.robot = (self.robot.0 + dir.0, self.robot.1 + dir.1);
+                self.shift_boxes(&pushed, dir);
+                Step {
+                    dir,
+                    pushed,
+                    blocked: false,
+                }
             }
+            None => Step {
+                dir,
+                pushed: vec![],
+                blocked: true,
+            },
+        }
+    }
 
-            Some(HashSet::new())
+    fn undo(&mut self, step: &Step) {
+        if step.blocked {
+            return;
         }
+        let (di, dj) = step.dir;
+        let moved: Vec<Coord> = step.pushed.iter().map(|(i, j)| (i + di, j + dj)).collect();
+        self.shift_boxes(&moved, (-di, -dj));
+        self.robot = (self.robot.0 - di, self.robot.1 - dj);
     }
 
-    pub fn parse(input: &str) -> (Map, Vec<Coord>) {
-        let (input_map, input_dirs) = input.trim().split_once("\n\n").unwrap();
+    fn render(&self) -> String {
+        let rows = self.walls.iter().map(|pos| pos.0 + 1).max().unwrap_or(0);
+        let cols = self.walls.iter().map(|pos| pos.1 + 1).max().unwrap_or(0);
+        let mut grid = vec![vec!['.'; cols as usize]; rows as usize];
+        for &(i, j) in &self.walls {
+            grid[i as usize][j as usize] = '#';
+        }
+        for (&(i, j), &width) in &self.boxes {
+            for (k, glyph) in box_glyphs(width).chars().enumerate() {
+                grid[i as usize][j as usize + k] = glyph;
+            }
+        }
+        grid[self.robot.0 as usize][self.robot.1 as usize] = '@';
+        grid.iter()
+            .map(|row| row.iter().collect::<String>() + "\n")
+            .join("")
+    }
 
-        let mut robot = (0, 0);
-        let mut boxes = HashSet::new();
-        let mut walls = HashSet::new();
-        input_map
-            .trim()
-            .lines()
-            .enumerate()
-            .flat_map(|(i, line)| {
-                line.trim()
-                    .chars()
-                    .enumerate()
-                    .map(move |(j, c)| ((i as isize, j as isize), c))
-            })
-            .for_each(|((i, j), c)| {
-                if c == '@' {
-                    robot = (i, j);
-                } else if c == 'O' {
-                    boxes.insert((i, j));
-                } else if c == '#' {
-                    walls.insert((i, j));
-                };
-            });
+    fn state(&self) -> State {
+        let boxes = self.boxes.iter().map(|(pos, width)| (*pos, *width));
+        (self.robot, boxes.sorted().collect())
+    }
 
-        let dirs = input_dirs
-            .trim()
-            .lines()
-            .flat_map(|line| line.trim().chars())
-            .map(|c| match c {
-                '<' => (0, -1),
-                '>' => (0, 1),
-                '^' => (-1, 0),
-                'v' => (1, 0),
-                _ => unreachable!(),
-            })
-            .collect();
+    fn load(&mut self, (robot, boxes): &State) {
+        self.robot = *robot;
+        self.boxes.clear();
+        self.box_tiles.clear();
+        for &(pos, width) in boxes {
+            self.add_box(pos, width);
+        }
+    }
 
-        (
-            Map {
-                robot,
-                boxes,
-                walls,
-            },
-            dirs,
-        )
+    // A box that walls keep from moving both vertically and horizontally can
+    // never be moved again. Moving up takes a free tile above all of it and a
+    // tile below it to be pushed from, and likewise for the other directions.
+    fn is_stuck(&self, (i, j): Coord, width: isize) -> bool {
+        let walls = |di: isize| {
+            (0..width)
+                .filter(|k| self.walls.contains(&(i + di, j + k)))
+                .count() as isize
+        };
+        let (above, below) = (walls(-1), walls(1));
+        let up = above == 0 && below < width;
+        let down = below == 0 && above < width;
+        let sideways = !self.walls.contains(&(i, j - 1)) && !self.walls.contains(&(i, j + width));
+        !up && !down && !sideways
     }
 }
 
-pub fn part1(input: &str) -> String {
-    let (mut map, dirs) = part1::parse(input);
+// What one move of the robot did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Step {
+    dir: Coord,
+    // The boxes pushed, by their leftmost tile before the move.
+    pushed: Vec<Coord>,
+    blocked: bool,
+}
 
-    for dir in dirs {
-        map.move_robot(dir);
+impl Step {
+    fn describe(&self) -> String {
+        let dir = dir_char(self.dir);
+        match (self.blocked, self.pushed.len()) {
+            (true, _) => format!("{dir} blocked"),
+            (false, 0) => format!("{dir}"),
+            (false, 1) => format!("{dir} pushed 1 box"),
+            (false, n) => format!("{dir} pushed {n} boxes"),
+        }
     }
+}
 
-    map.boxes_gps().iter().sum::<usize>().to_string()
+// Plays the robot's moves one at a time, keeping every step so that they can be
+// taken back.
+struct Replay {
+    map: Map,
+    dirs: Vec<Coord>,
+    steps: Vec<Step>,
 }
 
-mod part2 {
-    use super::*;
+impl Replay {
+    fn new(map: Map, dirs: Vec<Coord>) -> Replay {
+        Replay {
+            map,
+            dirs,
+            steps: vec![],
+        }
+    }
 
-    type Box = (Coord, Coord);
+    // Makes the next move, if there is one left.
+    fn step(&mut self) -> Option<&Step> {
+        let dir = *self.dirs.get(self.steps.len())?;
+        let step = self.map.move_robot(dir);
+        self.steps.push(step);
+        self.steps.last()
+    }
 
-    pub struct Map {
-        robot: Coord,
-        boxes: HashSet<Box>,
-        walls: HashSet<Coord>,
+    // Takes the last move back.
+    fn undo(&mut self) -> Option<Step> {
+        let step = self.steps.pop()?;
+        self.map.undo(&step);
+        Some(step)
     }
 
-    impl Map {
-        pub fn boxes_gps(&self) -> HashSet<usize> {
-            self.boxes
-                .iter()
-                .map(|((i, j), _)| (i * 100 + j) as usize)
-                .collect()
-        }
+    // Moves forwards or backwards until `moves` moves have been made.
+    fn seek(&mut self, moves: usize) {
+        while self.steps.len() > moves && self.undo().is_some() {}
+        while self.steps.len() < moves && self.step().is_some() {}
+    }
+}
 
-        pub fn move_robot(&mut self, dir: &Coord) {
-            if let Some(boxes) = self._move_check(&self.robot, dir) {
-                self.robot = (self.robot.0 + dir.0, self.robot.1 + dir.1);
-                self.boxes.retain(|b| !boxes.contains(b));
-                boxes.iter().for_each(|((i1, j1), (i2, j2))| {
-                    self.boxes
-                        .insert(((i1 + dir.0, j1 + dir.1), (i2 + dir.0, j2 + dir.1)));
-                });
+// The tiles of a box, `O` when it is one tile wide and `[`, `=`..., `]`
+// otherwise.
+fn box_glyphs(width: isize) -> String {
+    match width {
+        1 => "O".to_string(),
+        _ => format!("[{}]", "=".repeat(width as usize - 2)),
+    }
+}
+
+// Every tile of the map is stretched `scale` times horizontally, so that boxes
+// get `scale` times wider. Boxes already drawn wide as `[...]` are supported.
+fn parse_map(input_map: &str, scale: usize) -> Map {
+    let mut map = Map {
+        robot: (0, 0),
+        walls: HashSet::new(),
+        boxes: HashMap::new(),
+        box_tiles: HashMap::new(),
+    };
+    for (i, line) in input_map.trim().lines().enumerate() {
+        let line: String = line
+            .trim()
+            .chars()
+            .map(|c| match c {
+                '#' | '.' | '=' => c.to_string().repeat(scale),
+                '@' => "@".to_string() + &".".repeat(scale - 1),
+                'O' => box_glyphs(scale as isize),
+                '[' => "[".to_string() + &"=".repeat(scale - 1),
+                ']' => "=".repeat(scale - 1) + "]",
+                _ => unreachable!(),
+            })
+            .collect();
+        let mut box_start = None;
+        for (j, c) in line.chars().enumerate() {
+            let pos = (i as isize, j as isize);
+            match c {
+                '#' => {
+                    map.walls.insert(pos);
+                }
+                '@' => map.robot = pos,
+                'O' => map.add_box(pos, 1),
+                '[' => box_start = Some(pos),
+                ']' => {
+                    let start = box_start.take().unwrap();
+                    map.add_box(start, pos.1 - start.1 + 1);
+                }
+                _ => {}
             }
         }
+    }
+    map
+}
 
-        fn _move_check(&self, coord: &Coord, dir: &Coord) -> Option<HashSet<Box>> {
-            let next_coord = (coord.0 + dir.0, coord.1 + dir.1);
+fn parse(input: &str, scale: usize) -> (Map, Vec<Coord>) {
+    let (input_map, input_dirs) = input.trim().split_once("\n\n").unwrap();
+    let map = parse_map(input_map, scale);
+
+    let dirs = input_dirs
+        .trim()
+        .lines()
+        .flat_map(|line| line.trim().chars())
+        .map(|c| match c {
+            '<' => (0, -1),
+            '>' => (0, 1),
+            '^' => (-1, 0),
+            'v' => (1, 0),
+            _ => unreachable!(),
+        })
+        .collect();
+
+    (map, dirs)
+}
 
-            if self.walls.contains(&next_coord) {
-                return None;
-            }
+const DIRS: [Coord; 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
 
-            if let Some(b) = self._get_box(&next_coord) {
-                let edge_coords = self._get_edge_coords(b, dir);
-                return edge_coords
-                    .into_iter()
-                    .map(|c| self._move_check(&c, dir))
-                    .reduce(|acc, boxes| {
-                        if let (Some(mut acc), Some(boxes)) = (acc, boxes) {
-                            acc.extend(boxes);
-                            Some(acc)
-                        } else {
-                            None
-                        }
-                    })
-                    .flatten()
-                    .map(|mut boxes| {
-                        boxes.insert(*b);
-                        boxes
-                    });
-            }
+fn dir_char(dir: Coord) -> char {
+    match dir {
+        (0, -1) => '<',
+        (0, 1) => '>',
+        (-1, 0) => '^',
+        _ => 'v',
+    }
+}
 
-            Some(HashSet::new())
-        }
+const MAX_STATES: usize = 1_000_000;
+
+// A plan search that gave up after visiting this many states.
+#[derive(Debug, PartialEq, Eq)]
+struct SearchLimit(usize);
 
-        fn _get_box(&self, &(i, j): &Coord) -> Option<&Box> {
-            self.boxes
-                .get(&((i, j), (i, j + 1)))
-                .or_else(|| self.boxes.get(&((i, j - 1), (i, j))))
+// A lower bound on the moves left: every move shifts a box by at most one tile,
+// so no box can reach the closest target of its width any sooner.
+fn moves_left(boxes: &[(Coord, isize)], target: &[(Coord, isize)]) -> usize {
+    boxes
+        .iter()
+        .map(|&((i, j), width)| {
+            target
+                .iter()
+                .filter(|(_, target_width)| *target_width == width)
+                .map(|((ti, tj), _)| i.abs_diff(*ti) + j.abs_diff(*tj))
+                .min()
+                .unwrap_or(usize::MAX)
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+// Searches with A* for the fewest robot moves that bring the boxes to where
+// they are in `target`, skipping states with a box stuck off target. Gives up
+// once more than `max_states` states have been seen.
+fn plan(map: &Map, target: &Map, max_states: usize) -> Result<Option<Vec<Coord>>, SearchLimit> {
+    let mut map = map.clone();
+    let (_, target) = target.state();
+    let widths =
+        |boxes: &[(Coord, isize)]| boxes.iter().map(|(_, width)| *width).sorted().collect_vec();
+    let start = map.state();
+    if widths(&start.1) != widths(&target) {
+        return Ok(None);
+    }
+
+    let mut parents: HashMap<State, (usize, Option<(State, Coord)>)> =
+        HashMap::from([(start.clone(), (0, None))]);
+    let mut queue = BinaryHeap::from([Reverse((moves_left(&start.1, &target), 0, start))]);
+    while let Some(Reverse((_, moves, state))) = queue.pop() {
+        if moves > parents[&state].0 {
+            continue;
+        }
+        if state.1 == target {
+            let mut dirs = vec![];
+            let mut state = &state;
+            while let (_, Some((parent, dir))) = &parents[state] {
+                dirs.push(*dir);
+                state = parent;
+            }
+            dirs.reverse();
+            return Ok(Some(dirs));
+        }
+        if parents.len() > max_states {
+            return Err(SearchLimit(max_states));
         }
 
-        fn _get_edge_coords(&self, b: &Box, dir: &Coord) -> Vec<Coord> {
-            let (left, right) = *b;
-            if dir == &(0, 1) {
-                vec![right]
-            } else if dir == &(0, -1) {
-                vec![left]
-            } else {
-                vec![left, right]
+        map.load(&state);
+        for dir in DIRS {
+            let step = map.move_robot(dir);
+            if step.blocked {
+                continue;
+            }
+            let next = map.state();
+            let stuck = step.pushed.iter().any(|(i, j)| {
+                let pos = (i + dir.0, j + dir.1);
+                let width = map.boxes[&pos];
+                !target.contains(&(pos, width)) && map.is_stuck(pos, width)
+            });
+            map.undo(&step);
+            if stuck
+                || parents
+                    .get(&next)
+                    .is_some_and(|(known, _)| *known <= moves + 1)
+            {
+                continue;
             }
+            let estimate = moves + 1 + moves_left(&next.1, &target);
+            parents.insert(next.clone(), (moves + 1, Some((state.clone(), dir))));
+            queue.push(Reverse((estimate, moves + 1, next)));
         }
     }
+    Ok(None)
+}
 
-    pub fn parse(input: &str) -> (Map, Vec<Coord>) {
-        let (input_map, input_dirs) = input.trim().split_once("\n\n").unwrap();
+fn gps_after_moves(input: &str, scale: usize) -> usize {
+    let (mut map, dirs) = parse(input, scale);
 
-        let input_map_resized = input_map
-            .trim()
-            .lines()
-            .map(|line| {
-                line.trim()
-                    .chars()
-                    .map(|c| match c {
-                        '#' => "##",
-                        'O' => "[]",
-                        '.' => "..",
-                        '@' => "@.",
-                        _ => unreachable!(),
-                    })
-                    .collect::<Vec<_>>()
-                    .join("")
-            })
-            .collect::<Vec<_>>()
-            .join("\n");
+    for dir in dirs {
+        map.move_robot(dir);
+    }
 
-        let mut robot = (0, 0);
-        let mut boxes = HashSet::new();
-        let mut walls = HashSet::new();
-        input_map_resized
-            .trim()
-            .lines()
-            .enumerate()
-            .flat_map(|(i, line)| {
-                line.trim()
-                    .chars()
-                    .enumerate()
-                    .map(move |(j, c)| ((i as isize, j as isize), c))
-            })
-            .for_each(|((i, j), c)| {
-                if c == '@' {
-                    robot = (i, j);
-                } else if c == '[' {
-                    boxes.insert(((i, j), (i, j + 1)));
-                } else if c == '#' {
-                    walls.insert((i, j));
-                };
-            });
+    map.boxes_gps()
+}
 
-        let dirs = input_dirs
-            .trim()
-            .lines()
-            .flat_map(|line| line.trim().chars())
-            .map(|c| match c {
-                '<' => (0, -1),
-                '>' => (0, 1),
-                '^' => (-1, 0),
-                'v' => (1, 0),
-                _ => unreachable!(),
-            })
-            .collect();
+// The warehouse after each of the given numbers of moves.
+fn dump(input: &str, scale: usize, moves: &[usize]) -> String {
+    let (map, dirs) = parse(input, scale);
+    let mut replay = Replay::new(map, dirs);
+
+    moves
+        .iter()
+        .map(|&moves| {
+            replay.seek(moves);
+            let title = match replay.steps.last() {
+                Some(step) => format!("after move {} ({})", replay.steps.len(), step.describe()),
+                None => "initial state".to_string(),
+            };
+            format!("{title}:\n{}", replay.map.render())
+        })
+        .join("\n")
+}
 
-        (
-            Map {
-                robot,
-                boxes,
-                walls,
-            },
-            dirs,
-        )
+pub fn command(input: &str, args: &[&str]) -> Option<String> {
+    let (scale, args) = match args {
+        ["scale", scale, rest @ ..] => match scale.parse() {
+            Ok(scale) if scale >= 1 => (scale, rest),
+            _ => return None,
+        },
+        _ => (1, args),
+    };
+
+    match args {
+        [] => Some(format!("{}\n", gps_after_moves(input, scale))),
+        ["plan", target, rest @ ..] => {
+            let start = match rest {
+                [] => input.to_string(),
+                [start] => fs::read_to_string(start).ok()?,
+                _ => return None,
+            };
+            let start = start.trim().split("\n\n").next()?;
+            let map = parse_map(start, scale);
+            let target = parse_map(&fs::read_to_string(target).ok()?, scale);
+            match plan(&map, &target, MAX_STATES) {
+                Ok(Some(dirs)) => Some(format!(
+                    "{} moves\n{}\n",
+                    dirs.len(),
+                    dirs.into_iter().map(dir_char).collect::<String>()
+                )),
+                Ok(None) => Some("no plan found\n".to_string()),
+                Err(SearchLimit(states)) => Some(format!("search gave up after {states} states\n")),
+            }
+        }
+        ["dump", moves @ ..] if !moves.is_empty() => {
+            let moves: Vec<usize> = moves
+                .iter()
+                .map(|n| n.parse().ok())
+                .collect::<Option<_>>()?;
+            Some(dump(input, scale, &moves))
+        }
+        _ => None,
     }
 }
 
-pub fn part2(input: &str) -> String {
-    let (mut map, dirs) = part2::parse(input);
-
-    for dir in dirs {
-        map.move_robot(&dir);
-    }
+pub fn part1(input: &str) -> String {
+    gps_after_moves(input, 1).to_string()
+}
 
-    map.boxes_gps().iter().sum::<usize>().to_string()
+pub fn part2(input: &str) -> String {
+    gps_after_moves(input, 2).to_string()
 }
 
 #[cfg(test)]
@@ -350,4 +539,191 @@ mod tests {
         ";
         assert_eq!(part2(input2), "9021");
     }
+
+    #[test]
+    fn test_wide_boxes() {
+        let input = "
+        #######
+        #..O@.#
+        #######
+
+        <<<
+        ";
+        assert_eq!(gps_after_moves(input, 1), 101);
+        assert_eq!(gps_after_moves(input, 3), 106);
+
+        // Three-wide boxes overlapping by one tile push each other up.
+        let input = "
+        ##########
+        #........#
+        #..[=]...#
+        #....[=].#
+        #.....@..#
+        ##########
+
+        ^^
+        ";
+        assert_eq!(gps_after_moves(input, 1), 308);
+        let (map, _) = parse(input, 2);
+        assert_eq!(map.boxes[&(2, 6)], 6);
+    }
+
+    #[test]
+    fn test_replay() {
+        let input = "
+        ########
+        #..O.O.#
+        ##@.O..#
+        #...O..#
+        #.#.O..#
+        #...O..#
+        #......#
+        ########
+
+        <^^>>>vv<v>>v<<
+        ";
+        let (map, dirs) = parse(input, 1);
+        let initial = map.render();
+        let mut replay = Replay::new(map, dirs);
+
+        assert!(replay.step().unwrap().blocked);
+        replay.seek(5);
+        assert_eq!(
+            replay.steps.last(),
+            Some(&Step {
+                dir: (0, 1),
+                pushed: vec![(1, 4), (1, 5)],
+                blocked: false,
+            })
+        );
+        replay.seek(15);
+        assert_eq!(
+            replay.map.render(),
+            "########\n#....OO#\n##.....#\n#.....O#\n#.#O@..#\n#...O..#\n#...O..#\n########\n"
+        );
+        assert!(replay.step().is_none());
+
+        while replay.undo().is_some() {}
+        assert_eq!(replay.map.render(), initial);
+    }
+
+    #[test]
+    fn test_dump() {
+        let input = "
+        #######
+        #...#.#
+        #.....#
+        #..OO@#
+        #..O..#
+        #.....#
+        #######
+
+        <vv<<^^<<^^
+        ";
+
+        assert_eq!(
+            command(input, &["scale", "2", "dump", "0", "11", "1"]),
+            Some(
+                "initial state:\n\
+                ##############\n\
+                ##......##..##\n\
+                ##..........##\n\
+                ##....[][]@.##\n\
+                ##....[]....##\n\
+                ##..........##\n\
+                ##############\n\
+                \n\
+                after move 11 (^ pushed 1 box):\n\
+                ##############\n\
+                ##...[].##..##\n\
+                ##...@.[]...##\n\
+                ##....[]....##\n\
+                ##..........##\n\
+                ##..........##\n\
+                ##############\n\
+                \n\
+                after move 1 (< pushed 2 boxes):\n\
+                ##############\n\
+                ##......##..##\n\
+                ##..........##\n\
+                ##...[][]@..##\n\
+                ##....[]....##\n\
+                ##..........##\n\
+                ##############\n"
+                    .to_string()
+            )
+        );
+    }
+
+    fn check_plan(start: &str, target: &str, scale: usize) -> Option<usize> {
+        let mut map = parse_map(start, scale);
+        let target = parse_map(target, scale);
+        let dirs = plan(&map, &target, MAX_STATES).unwrap()?;
+
+        for &dir in &dirs {
+            map.move_robot(dir);
+        }
+        assert_eq!(map.state().1, target.state().1);
+        Some(dirs.len())
+    }
+
+    #[test]
+    fn test_plan() {
+        let start = "
+        ######
+        #....#
+        #@O..#
+        #....#
+        ######
+        ";
+        let target = "
+        ######
+        #....#
+        #...O#
+        #....#
+        ######
+        ";
+        assert_eq!(check_plan(start, target, 1), Some(2));
+
+        // The robot has to walk around the box to push it back.
+        let target = "
+        ######
+        #....#
+        #O...#
+        #....#
+        ######
+        ";
+        assert_eq!(check_plan(start, target, 1), Some(5));
+        // Too few states to find it.
+        let (map, target_map) = (parse_map(start, 1), parse_map(target, 1));
+        assert_eq!(plan(&map, &target_map, 10), Err(SearchLimit(10)));
+
+        // Wide boxes have to be pushed up from below either half.
+        let target = "
+        ######
+        #.O..#
+        #....#
+        #....#
+        ######
+        ";
+        assert_eq!(check_plan(start, target, 2), Some(4));
+
+        // Once in a corner, the box can never leave it.
+        let start = "
+        ######
+        #O...#
+        #..@.#
+        #....#
+        ######
+        ";
+        let target = "
+        ######
+        #....#
+        #..O.#
+        #....#
+        ######
+        ";
+        assert_eq!(check_plan(start, target, 1), None);
+        assert_eq!(check_plan(start, target, 2), None);
+    }
 }
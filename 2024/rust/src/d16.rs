@@ -39,6 +39,14 @@ impl Reindeer {
         }
     }
 
+    fn u_turn(&self) -> Self {
+        Reindeer {
+            pos: self.pos,
+            dir: (-self.dir.0, -self.dir.1),
+            can_turn: false,
+        }
+    }
+
     fn ccw(&self) -> Self {
         Reindeer {
             pos: self.pos,
@@ -85,17 +93,34 @@ fn parse(input: &str) -> (Map, Reindeer, Vec<Reindeer>) {
     (map, start, ends)
 }
 
-fn neighbors(reindeer: Reindeer, map: &Map) -> Vec<(Reindeer, usize)> {
+#[derive(Debug, Clone, Copy)]
+struct Costs {
+    forward: usize,
+    turn: usize,
+    // Turning around on the spot, which is not allowed when `None`.
+    u_turn: Option<usize>,
+}
+
+const DEFAULT_COSTS: Costs = Costs {
+    forward: 1,
+    turn: 1000,
+    u_turn: None,
+};
+
+fn neighbors(reindeer: Reindeer, map: &Map, costs: &Costs) -> Vec<(Reindeer, usize)> {
     let mut result = Vec::new();
 
     let forward = reindeer.forward();
     if *map.get(&forward.pos).unwrap_or(&'#') != '#' {
-        result.push((forward, 1));
+        result.push((forward, costs.forward));
     }
 
     if reindeer.can_turn {
-        result.push((reindeer.cw(), 1000));
-        result.push((reindeer.ccw(), 1000));
+        result.push((reindeer.cw(), costs.turn));
+        result.push((reindeer.ccw(), costs.turn));
+        if let Some(u_turn) = costs.u_turn {
+            result.push((reindeer.u_turn(), u_turn));
+        }
     }
     result
 }
@@ -103,6 +128,7 @@ fn neighbors(reindeer: Reindeer, map: &Map) -> Vec<(Reindeer, usize)> {
 fn dijkstra(
     map: &Map,
     start: Reindeer,
+    step_costs: &Costs,
 ) -> (HashMap<Reindeer, usize>, HashMap<Reindeer, Vec<Reindeer>>) {
     let mut costs = HashMap::new();
     let mut trace = HashMap::new();
@@ -113,7 +139,7 @@ fn dijkstra(
         if cost > costs[&reindeer] {
             continue;
         }
-        for (neighbor, step_cost) in neighbors(reindeer, map) {
+        for (neighbor, step_cost) in neighbors(reindeer, map, step_costs) {
             let cost = cost + step_cost;
             if cost < *costs.get(&neighbor).unwrap_or(&usize::MAX) {
                 costs.insert(neighbor, cost);
@@ -127,32 +153,297 @@ fn dijkstra(
     (costs, trace)
 }
 
-pub fn part1(input: &str) -> String {
-    let (map, start, ends) = parse(input);
-    let (costs, _trace) = dijkstra(&map, start);
-    let min_cost = *ends.iter().filter_map(|end| costs.get(end)).min().unwrap();
-    min_cost.to_string()
-}
-
-pub fn part2(input: &str) -> String {
+fn lowest_cost(input: &str, step_costs: &Costs) -> Option<(usize, usize)> {
     let (map, start, ends) = parse(input);
-    let (costs, trace) = dijkstra(&map, start);
+    let (costs, trace) = dijkstra(&map, start, step_costs);
 
-    let min_cost = *ends.iter().filter_map(|end| costs.get(end)).min().unwrap();
+    let min_cost = *ends.iter().filter_map(|end| costs.get(end)).min()?;
     let min_cost_ends = ends
         .iter()
         .filter(|end| costs.get(end).filter(|&c| *c == min_cost).is_some())
         .collect_vec();
 
     let mut coords = HashSet::new();
-    let mut pool = Vec::from(min_cost_ends);
+    let mut pool = min_cost_ends;
     while let Some(reindeer) = pool.pop() {
         coords.insert(reindeer.pos);
-        if let Some(prev) = trace.get(&reindeer) {
+        if let Some(prev) = trace.get(reindeer) {
             prev.iter().for_each(|prev| pool.push(prev));
         }
     }
-    coords.len().to_string()
+    Some((min_cost, coords.len()))
+}
+
+type Route = Vec<Reindeer>;
+
+fn route_cost(route: &[Reindeer], map: &Map, costs: &Costs) -> usize {
+    route
+        .iter()
+        .tuple_windows()
+        .map(|(from, to)| {
+            neighbors(*from, map, costs)
+                .into_iter()
+                .find(|(neighbor, _)| neighbor == to)
+                .unwrap()
+                .1
+        })
+        .sum()
+}
+
+// The cost of the cheapest way on to `end` from every reindeer that `start`
+// can reach, found by searching backwards from `end`. Routes stop on reaching
+// `end`, so no step leads on from there.
+fn remaining_costs(
+    map: &Map,
+    start: Reindeer,
+    end: Coord,
+    costs: &Costs,
+) -> HashMap<Reindeer, usize> {
+    let mut prev_steps: HashMap<Reindeer, Vec<(Reindeer, usize)>> = HashMap::new();
+    let mut visited = HashSet::from([start]);
+    let mut stack = vec![start];
+    while let Some(reindeer) = stack.pop() {
+        if reindeer.pos == end {
+            continue;
+        }
+        for (neighbor, step_cost) in neighbors(reindeer, map, costs) {
+            prev_steps
+                .entry(neighbor)
+                .or_default()
+                .push((reindeer, step_cost));
+            if visited.insert(neighbor) {
+                stack.push(neighbor);
+            }
+        }
+    }
+
+    let mut remaining: HashMap<Reindeer, usize> = visited
+        .into_iter()
+        .filter(|reindeer| reindeer.pos == end)
+        .map(|reindeer| (reindeer, 0))
+        .collect();
+    let mut min_heap: BinaryHeap<_> = remaining
+        .keys()
+        .map(|reindeer| Reverse((0, *reindeer)))
+        .collect();
+    while let Some(Reverse((cost, reindeer))) = min_heap.pop() {
+        if cost > remaining[&reindeer] {
+            continue;
+        }
+        for (prev, step_cost) in prev_steps.get(&reindeer).into_iter().flatten() {
+            let cost = cost + step_cost;
+            if cost < *remaining.get(prev).unwrap_or(&usize::MAX) {
+                remaining.insert(*prev, cost);
+                min_heap.push(Reverse((cost, *prev)));
+            }
+        }
+    }
+    remaining
+}
+
+// The cheapest route from `start` to any reindeer on `end`, avoiding the
+// `banned` reindeer and steps. Searches with A*, as bans only ever make the
+// `remaining` costs to `end` higher.
+fn cheapest_route(
+    map: &Map,
+    start: Reindeer,
+    end: Coord,
+    costs: &Costs,
+    remaining: &HashMap<Reindeer, usize>,
+    banned: &HashSet<Reindeer>,
+    banned_steps: &HashSet<(Reindeer, Reindeer)>,
+) -> Option<(usize, Route)> {
+    let mut best = HashMap::from([(start, 0)]);
+    let mut parents: HashMap<Reindeer, Reindeer> = HashMap::new();
+    let mut min_heap = BinaryHeap::from([Reverse((*remaining.get(&start)?, 0, start))]);
+    while let Some(Reverse((_, cost, reindeer))) = min_heap.pop() {
+        if cost > best[&reindeer] {
+            continue;
+        }
+        if reindeer.pos == end {
+            let mut route = vec![reindeer];
+            while let Some(parent) = parents.get(route.last().unwrap()) {
+                route.push(*parent);
+            }
+            route.reverse();
+            return Some((cost, route));
+        }
+        for (neighbor, step_cost) in neighbors(reindeer, map, costs) {
+            if banned.contains(&neighbor) || banned_steps.contains(&(reindeer, neighbor)) {
+                continue;
+            }
+            let Some(left) = remaining.get(&neighbor) else {
+                continue;
+            };
+            let cost = cost + step_cost;
+            if cost < *best.get(&neighbor).unwrap_or(&usize::MAX) {
+                best.insert(neighbor, cost);
+                parents.insert(neighbor, reindeer);
+                min_heap.push(Reverse((cost + left, cost, neighbor)));
+            }
+        }
+    }
+    None
+}
+
+// A route waiting to be picked as the next cheapest, or a spur search off the
+// `i`th reindeer of a found route that is only run once no cheaper candidate
+// is left.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Candidate {
+    Route(Route),
+    Spur { route: usize, i: usize },
+}
+
+// Yen's algorithm: every next cheapest route leaves one of the routes already
+// found at some reindeer, by a step none of the routes sharing its prefix took.
+// The spur searches are queued with a lower bound on the cost of the routes
+// they can find, so that most of them never have to run.
+fn cheapest_routes(
+    map: &Map,
+    start: Reindeer,
+    end: Coord,
+    k: usize,
+    costs: &Costs,
+) -> Vec<(usize, Route)> {
+    let remaining = remaining_costs(map, start, end, costs);
+    let Some(first) = cheapest_route(
+        map,
+        start,
+        end,
+        costs,
+        &remaining,
+        &HashSet::new(),
+        &HashSet::new(),
+    ) else {
+        return vec![];
+    };
+
+    let mut routes = vec![];
+    let mut seen: HashSet<Route> = HashSet::from([first.1.clone()]);
+    let mut candidates = BinaryHeap::from([Reverse((first.0, Candidate::Route(first.1)))]);
+    while routes.len() < k {
+        let Some(Reverse((cost, candidate))) = candidates.pop() else {
+            break;
+        };
+        match candidate {
+            Candidate::Route(route) => {
+                let mut root_cost = 0;
+                for i in 0..route.len() - 1 {
+                    let spur = Candidate::Spur {
+                        route: routes.len(),
+                        i,
+                    };
+                    candidates.push(Reverse((root_cost + remaining[&route[i]], spur)));
+                    root_cost += route_cost(&route[i..=i + 1], map, costs);
+                }
+                routes.push((cost, route));
+            }
+            Candidate::Spur { route, i } => {
+                let root = &routes[route].1[..=i];
+                let banned_steps: HashSet<(Reindeer, Reindeer)> = routes
+                    .iter()
+                    .filter(|(_, route)| route.len() > i + 1 && route[..=i] == *root)
+                    .map(|(_, route)| (route[i], route[i + 1]))
+                    .collect();
+                let banned: HashSet<Reindeer> = root[..i].iter().copied().collect();
+                let Some((_, spur)) =
+                    cheapest_route(map, root[i], end, costs, &remaining, &banned, &banned_steps)
+                else {
+                    continue;
+                };
+                let route: Route = root[..i].iter().chain(&spur).copied().collect();
+                if seen.insert(route.clone()) {
+                    let cost = route_cost(&route, map, costs);
+                    candidates.push(Reverse((cost, Candidate::Route(route))));
+                }
+            }
+        }
+    }
+    routes
+}
+
+fn render_route(map: &Map, route: &[Reindeer]) -> String {
+    let arrows: HashMap<Coord, char> = route
+        .iter()
+        .map(|reindeer| {
+            let arrow = match reindeer.dir {
+                (0, 1) => '>',
+                (1, 0) => 'v',
+                (0, -1) => '<',
+                _ => '^',
+            };
+            (reindeer.pos, arrow)
+        })
+        .collect();
+    let rows = map.keys().map(|pos| pos.0 + 1).max().unwrap_or(0);
+    let cols = map.keys().map(|pos| pos.1 + 1).max().unwrap_or(0);
+
+    (0..rows)
+        .map(|i| {
+            (0..cols)
+                .map(|j| match (map[&(i, j)], arrows.get(&(i, j))) {
+                    ('.', Some(arrow)) => *arrow,
+                    (c, _) => c,
+                })
+                .collect::<String>()
+                + "\n"
+        })
+        .join("")
+}
+
+fn routes(input: &str, k: usize, costs: &Costs) -> String {
+    let (map, start, ends) = parse(input);
+    let routes = cheapest_routes(&map, start, ends[0].pos, k, costs);
+    if routes.is_empty() {
+        return "no route\n".to_string();
+    }
+
+    routes
+        .iter()
+        .enumerate()
+        .map(|(i, (cost, route))| {
+            format!(
+                "route {}: cost {cost}\n{}",
+                i + 1,
+                render_route(&map, route)
+            )
+        })
+        .join("\n")
+}
+
+pub fn command(input: &str, args: &[&str]) -> Option<String> {
+    let (name, flags) = args.split_first()?;
+    let (k, flags) = match *name {
+        "routes" => (Some(flags.first()?.parse().ok()?), &flags[1..]),
+        "cost" => (None, flags),
+        _ => return None,
+    };
+    let mut costs = DEFAULT_COSTS;
+    for flag in flags.chunks(2) {
+        match flag {
+            ["--forward", n] => costs.forward = n.parse().ok()?,
+            ["--turn", n] => costs.turn = n.parse().ok()?,
+            ["--u-turn", n] => costs.u_turn = Some(n.parse().ok()?),
+            _ => return None,
+        }
+    }
+
+    match k {
+        Some(k) => Some(routes(input, k, &costs)),
+        None => match lowest_cost(input, &costs) {
+            Some((cost, tiles)) => Some(format!("cost {cost}, {tiles} tiles on the best routes\n")),
+            None => Some("no route\n".to_string()),
+        },
+    }
+}
+
+pub fn part1(input: &str) -> String {
+    lowest_cost(input, &DEFAULT_COSTS).unwrap().0.to_string()
+}
+
+pub fn part2(input: &str) -> String {
+    lowest_cost(input, &DEFAULT_COSTS).unwrap().1.to_string()
 }
 
 #[cfg(test)]
@@ -244,4 +535,87 @@ mod tests {
         ";
         assert_eq!(part2(input2), "64");
     }
+
+    #[test]
+    fn test_costs() {
+        let input = "
+        #####
+        #E.S#
+        #####
+        ";
+        assert_eq!(lowest_cost(input, &DEFAULT_COSTS), None);
+        let costs = Costs {
+            u_turn: Some(1500),
+            ..DEFAULT_COSTS
+        };
+        assert_eq!(lowest_cost(input, &costs), Some((1502, 3)));
+
+        let input = "
+        #####
+        #...#
+        #S#E#
+        #####
+        ";
+        assert_eq!(lowest_cost(input, &DEFAULT_COSTS), Some((3004, 5)));
+        let costs = Costs {
+            forward: 10,
+            turn: 1,
+            u_turn: None,
+        };
+        assert_eq!(lowest_cost(input, &costs), Some((43, 5)));
+    }
+
+    #[test]
+    fn test_cheapest_routes() {
+        let input = "
+        ###############
+        #.......#....E#
+        #.#.###.#.###.#
+        #.....#.#...#.#
+        #.###.#####.#.#
+        #.#.#.......#.#
+        #.#.#####.###.#
+        #...........#.#
+        ###.#.#####.#.#
+        #...#.....#.#.#
+        #.#.#.###.#.#.#
+        #.....#...#.#.#
+        #.###.#.#.#.#.#
+        #S..#.....#...#
+        ###############
+        ";
+        let (map, start, ends) = parse(input);
+        let routes = cheapest_routes(&map, start, ends[0].pos, 6, &DEFAULT_COSTS);
+
+        let costs = routes.iter().map(|(cost, _)| *cost).collect_vec();
+        assert_eq!(costs.len(), 6);
+        assert!(costs.is_sorted());
+        assert_eq!(costs[..3], [7036, 7036, 7036]);
+        assert!(costs[3] > 7036);
+        assert_eq!(routes.iter().map(|(_, route)| route).unique().count(), 6);
+        let best_tiles = routes[..3]
+            .iter()
+            .flat_map(|(_, route)| route.iter().map(|reindeer| reindeer.pos))
+            .unique()
+            .count();
+        assert_eq!(best_tiles, 45);
+        for (cost, route) in &routes {
+            assert_eq!(route_cost(route, &map, &DEFAULT_COSTS), *cost);
+        }
+
+        let rendered = render_route(&map, &routes[0].1);
+        assert!(rendered.lines().nth(13).unwrap().starts_with("#S"));
+        assert_eq!(rendered.matches(['>', '<', '^', 'v']).count(), 35);
+
+        let input = "
+        #####
+        #S#E#
+        #####
+        ";
+        assert_eq!(
+            command(input, &["routes", "3"]),
+            Some("no route\n".to_string())
+        );
+        assert_eq!(command(input, &["cost"]), Some("no route\n".to_string()));
+    }
 }
@@ -21,7 +21,7 @@ mod d25;
 use std::env;
 
 fn usage() -> ! {
-    eprintln!("Usage: ./aoc2024 day <number> [part <number>]");
+    eprintln!("Usage: ./aoc2024 day <number> [part <number> | <command> [<args>...]]");
     std::process::exit(1);
 }
 
@@ -44,43 +44,86 @@ fn solve(part: u32, part1_fn: fn(&str) -> String, part2_fn: fn(&str) -> String,
     }
 }
 
+fn input(day: u32) -> &'static str {
+    match day {
+        7 => include_str!("../../data/d07.txt"),
+        8 => include_str!("../../data/d08.txt"),
+        9 => include_str!("../../data/d09.txt"),
+        10 => include_str!("../../data/d10.txt"),
+        11 => include_str!("../../data/d11.txt"),
+        12 => include_str!("../../data/d12.txt"),
+        13 => include_str!("../../data/d13.txt"),
+        14 => include_str!("../../data/d14.txt"),
+        15 => include_str!("../../data/d15.txt"),
+        16 => include_str!("../../data/d16.txt"),
+        17 => include_str!("../../data/d17.txt"),
+        18 => include_str!("../../data/d18.txt"),
+        19 => include_str!("../../data/d19.txt"),
+        20 => include_str!("../../data/d20.txt"),
+        21 => include_str!("../../data/d21.txt"),
+        22 => include_str!("../../data/d22.txt"),
+        23 => include_str!("../../data/d23.txt"),
+        24 => include_str!("../../data/d24.txt"),
+        25 => include_str!("../../data/d25.txt"),
+        _ => unimplemented!(),
+    }
+}
+
 fn run(day: u32, part: u32) {
-    let solve = |part1_fn, part2_fn, input| solve(part, part1_fn, part2_fn, input);
+    let solve = |part1_fn, part2_fn| solve(part, part1_fn, part2_fn, input(day));
     match day {
-        7 => solve(d07::part1, d07::part2, include_str!("../../data/d07.txt")),
-        8 => solve(d08::part1, d08::part2, include_str!("../../data/d08.txt")),
-        9 => solve(d09::part1, d09::part2, include_str!("../../data/d09.txt")),
-        10 => solve(d10::part1, d10::part2, include_str!("../../data/d10.txt")),
-        11 => solve(d11::part1, d11::part2, include_str!("../../data/d11.txt")),
-        12 => solve(d12::part1, d12::part2, include_str!("../../data/d12.txt")),
-        13 => solve(d13::part1, d13::part2, include_str!("../../data/d13.txt")),
-        14 => solve(d14::part1, d14::part2, include_str!("../../data/d14.txt")),
-        15 => solve(d15::part1, d15::part2, include_str!("../../data/d15.txt")),
-        16 => solve(d16::part1, d16::part2, include_str!("../../data/d16.txt")),
-        17 => solve(d17::part1, d17::part2, include_str!("../../data/d17.txt")),
-        18 => solve(d18::part1, d18::part2, include_str!("../../data/d18.txt")),
-        19 => solve(d19::part1, d19::part2, include_str!("../../data/d19.txt")),
-        20 => solve(d20::part1, d20::part2, include_str!("../../data/d20.txt")),
-        21 => solve(d21::part1, d21::part2, include_str!("../../data/d21.txt")),
-        22 => solve(d22::part1, d22::part2, include_str!("../../data/d22.txt")),
-        23 => solve(d23::part1, d23::part2, include_str!("../../data/d23.txt")),
-        24 => solve(d24::part1, d24::part2, include_str!("../../data/d24.txt")),
-        25 => solve(d25::part1, d25::part2, include_str!("../../data/d25.txt")),
+        7 => solve(d07::part1, d07::part2),
+        8 => solve(d08::part1, d08::part2),
+        9 => solve(d09::part1, d09::part2),
+        10 => solve(d10::part1, d10::part2),
+        11 => solve(d11::part1, d11::part2),
+        12 => solve(d12::part1, d12::part2),
+        13 => solve(d13::part1, d13::part2),
+        14 => solve(d14::part1, d14::part2),
+        15 => solve(d15::part1, d15::part2),
+        16 => solve(d16::part1, d16::part2),
+        17 => solve(d17::part1, d17::part2),
+        18 => solve(d18::part1, d18::part2),
+        19 => solve(d19::part1, d19::part2),
+        20 => solve(d20::part1, d20::part2),
+        21 => solve(d21::part1, d21::part2),
+        22 => solve(d22::part1, d22::part2),
+        23 => solve(d23::part1, d23::part2),
+        24 => solve(d24::part1, d24::part2),
+        25 => solve(d25::part1, d25::part2),
         _ => unimplemented!(),
     }
 }
 
+fn command(day: u32, args: &[&str]) -> Option<String> {
+    match day {
+        8 => d08::command(input(day), args),
+        9 => d09::command(input(day), args),
+        10 => d10::command(input(day), args),
+        11 => d11::command(input(day), args),
+        12 => d12::command(input(day), args),
+        13 => d13::command(input(day), args),
+        14 => d14::command(input(day), args),
+        15 => d15::command(input(day), args),
+        16 => d16::command(input(day), args),
+        _ => None,
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
 
-    let (day, part) = match args
-        .iter()
-        .map(String::as_str)
-        .collect::<Vec<_>>()
-        .as_slice()
-    {
-        &[_, "day", day] => (day, "0"),
-        &[_, "day", day, "part", part] if part == "1" || part == "2" => (day, part),
+    let (day, part) = match *args.as_slice() {
+        [_, "day", day] => (day, "0"),
+        [_, "day", day, "part", part] if part == "1" || part == "2" => (day, part),
+        [_, "day", day, ref rest @ ..] => {
+            match day.parse().ok().and_then(|day| command(day, rest)) {
+                Some(output) => print!("{}", output),
+                None => usage(),
+            }
+            return;
+        }
         _ => usage(),
     };
 